@@ -0,0 +1,454 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use color_eyre::eyre::{WrapErr, eyre};
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::style::Color;
+use serde::Deserialize;
+
+/// Settings of `$XDG_CONFIG_HOME/bookmarks/config.toml`, command line flags win over them.
+///
+/// ```toml
+/// data = "~/bookmarks"
+/// log = "~/.local/state/bookmarks/log.jsonl"
+///
+/// # More data directories, after `data`
+/// [collections]
+/// work = "~/work/bookmarks"
+///
+/// [find]
+/// limit = 10
+///
+/// # New key = key it acts as in the bookmark list, popups only know the built in keys
+/// [keys]
+/// down = "j"
+/// up = "k"
+/// "c-o" = "o"
+///
+/// [theme]
+/// accent = "magenta"
+/// highlight = "#ffaf00"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub data: Option<PathBuf>,
+    #[serde(deserialize_with = "deserialize_collections")]
+    pub collections: Vec<Collection>,
+    /// Log file of the interactive interface
+    pub log: Option<PathBuf>,
+    pub find: FindConfig,
+    pub keys: Keymap,
+    pub theme: Theme,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FindConfig {
+    pub limit: Option<usize>,
+}
+
+/// `$XDG_CONFIG_HOME/bookmarks/config.toml`, `~/.config` when the variable is unset
+pub fn default_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|it| !it.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))?;
+    Some(config_home.join("bookmarks").join("config.toml"))
+}
+
+/// Expands a leading `~` to the home directory
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path,
+    }
+}
+
+impl Config {
+    /// Reads `path`, or the default file when there is one
+    pub fn load(path: Option<&Path>) -> color_eyre::Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let content =
+            fs::read_to_string(&path).wrap_err_with(|| format!("Reading {path:?} failed"))?;
+        let mut config: Self =
+            toml::from_str(&content).wrap_err_with(|| format!("Invalid config {path:?}"))?;
+        config.data = config.data.map(expand_home);
+        config.log = config.log.map(expand_home);
+        Ok(config)
+    }
+
+    /// `flags` when given, else the configured data directories, else `./sample-data`
+    pub fn collections(&self, flags: &[Collection]) -> color_eyre::Result<Vec<Collection>> {
+        let mut collections = match flags {
+            [] => self
+                .data
+                .iter()
+                .map(|path| Collection::new(path.clone()))
+                .chain(self.collections.iter().cloned())
+                .collect(),
+            flags => flags.to_vec(),
+        };
+        if collections.is_empty() {
+            collections.push(Collection::new(PathBuf::from("./sample-data")));
+        }
+        for (i, collection) in collections.iter().enumerate() {
+            if collections[..i].iter().any(|it| it.name == collection.name) {
+                return Err(eyre!("Collection {:?} is defined twice", collection.name));
+            }
+        }
+        Ok(collections)
+    }
+}
+
+/// A named data directory, `name=path` on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collection {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Collection {
+    /// Named after the directory
+    pub fn new(path: PathBuf) -> Self {
+        let name = path
+            .canonicalize()
+            .unwrap_or_else(|_| path.clone())
+            .file_name()
+            .map(|it| it.to_string_lossy().to_string())
+            .unwrap_or_else(|| "data".to_string());
+        Self { name, path }
+    }
+}
+
+impl FromStr for Collection {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, path)) if !name.is_empty() && !name.contains(['/', ':']) => Ok(Self {
+                name: name.to_string(),
+                path: PathBuf::from(path),
+            }),
+            _ if s.is_empty() => Err(eyre!("Empty data directory")),
+            _ => Ok(Self::new(PathBuf::from(s))),
+        }
+    }
+}
+
+/// `name = "path"` entries in their order
+fn deserialize_collections<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Collection>, D::Error> {
+    toml::Table::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, path)| match path {
+            toml::Value::String(path) if !name.contains(['/', ':']) => Ok(Collection {
+                name,
+                path: expand_home(path.into()),
+            }),
+            _ => Err(serde::de::Error::custom(format!(
+                "Invalid collection {name:?}, expected name = \"path\""
+            ))),
+        })
+        .collect()
+}
+
+/// A key press as written in the config and the status line, e.g. `j`, `c-o`, `return`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    /// Only control and alt, shift is part of the character
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self {
+            code,
+            modifiers: modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        const PREFIXES: [(&str, KeyModifiers); 3] = [
+            ("c-", KeyModifiers::CONTROL),
+            ("a-", KeyModifiers::ALT),
+            ("m-", KeyModifiers::ALT),
+        ];
+        // A prefix without a key after it is the key itself, e.g. `c-` is not a modifier
+        while let Some((modifier, tail)) = PREFIXES.iter().find_map(|(prefix, modifier)| {
+            Some((
+                *modifier,
+                rest.strip_prefix(prefix).filter(|it| !it.is_empty())?,
+            ))
+        }) {
+            modifiers |= modifier;
+            rest = tail;
+        }
+        let code = match rest {
+            "return" | "enter" => KeyCode::Enter,
+            "space" => KeyCode::Char(' '),
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            _ if rest.chars().count() == 1 => KeyCode::Char(rest.chars().next().unwrap_or(' ')),
+            _ if let Some(n) = rest.strip_prefix('f').and_then(|it| it.parse().ok()) => {
+                KeyCode::F(n)
+            }
+            _ => return Err(eyre!("Unknown key {s:?}")),
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "c-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "a-")?;
+        }
+        match self.code {
+            KeyCode::Enter => write!(f, "return"),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(ch) => write!(f, "{ch}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            code => write!(f, "{}", format!("{code:?}").to_lowercase()),
+        }
+    }
+}
+
+/// Extra keys acting as built in ones, the built in keys keep working.
+///
+/// Only the bookmark list, content and folder panes look keys up here, popups and
+/// text inputs use the built in keys.
+#[derive(Debug, Default, Clone)]
+pub struct Keymap(HashMap<Key, Key>);
+
+impl<'de> Deserialize<'de> for Keymap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let keys = HashMap::<String, String>::deserialize(deserializer)?;
+        keys.iter()
+            .map(|(from, to)| Ok((from.parse()?, to.parse()?)))
+            .collect::<color_eyre::Result<_>>()
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Keymap {
+    /// The built in key `key` acts as, `None` when it is not remapped
+    pub fn get(&self, key: Key) -> Option<Key> {
+        self.0.get(&key).copied()
+    }
+
+    /// Status line help like `Next: j | Prev: k` with the keys remapped to them, e.g. `Next: down/j`
+    pub fn describe(&self, help: &str) -> String {
+        help.split(" | ")
+            .map(|entry| {
+                let Some((label, key)) = entry.rsplit_once(": ") else {
+                    return entry.to_string();
+                };
+                let Ok(key) = key.parse::<Key>() else {
+                    return entry.to_string();
+                };
+                let mut keys: Vec<String> = self
+                    .0
+                    .iter()
+                    .filter(|(_, to)| **to == key)
+                    .map(|(from, _)| from.to_string())
+                    .collect();
+                keys.sort();
+                keys.push(key.to_string());
+                format!("{label}: {}", keys.join("/"))
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+/// Colours of the interactive interface
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Focused block borders and popups
+    pub accent: Color,
+    /// Status line help
+    pub help: Color,
+    /// Matched characters of search results
+    pub highlight: Color,
+    pub url: Color,
+    /// Folders and file paths
+    pub folder: Color,
+    /// Secondary text, like the urls in the bookmark list and tag labels
+    pub muted: Color,
+    /// Search positions and scores, field names
+    pub info: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            accent: Color::Yellow,
+            help: Color::Blue,
+            highlight: Color::Yellow,
+            url: Color::Blue,
+            folder: Color::Gray,
+            muted: Color::DarkGray,
+            info: Color::Blue,
+            success: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(s: &str) -> Key {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_keys() {
+        assert_eq!(key("j"), Key::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        assert_eq!(key("J"), Key::new(KeyCode::Char('J'), KeyModifiers::NONE));
+        assert_eq!(
+            key("c-o"),
+            Key::new(KeyCode::Char('o'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            key("c-a-x"),
+            Key::new(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+        assert_eq!(key("m-x"), key("a-x"));
+        assert_eq!(key("enter"), key("return"));
+        assert_eq!(
+            key("space"),
+            Key::new(KeyCode::Char(' '), KeyModifiers::NONE)
+        );
+        assert_eq!(key("f12"), Key::new(KeyCode::F(12), KeyModifiers::NONE));
+        assert_eq!(key("c-pagedown").code, KeyCode::PageDown);
+        // Multibyte characters, also behind modifiers
+        assert_eq!(key("€"), Key::new(KeyCode::Char('€'), KeyModifiers::NONE));
+        assert_eq!(
+            key("c-€"),
+            Key::new(KeyCode::Char('€'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(key("a-ö"), Key::new(KeyCode::Char('ö'), KeyModifiers::ALT));
+        assert!("€€".parse::<Key>().is_err());
+        // A lone modifier like `-` is a key of its own
+        assert_eq!(
+            key("c--"),
+            Key::new(KeyCode::Char('-'), KeyModifiers::CONTROL)
+        );
+        // Shift is part of the character
+        assert_eq!(Key::new(KeyCode::Char('J'), KeyModifiers::SHIFT), key("J"));
+
+        for unknown in ["", "jj", "ctrl-j", "c-", "s-j", "fx", "f99999"] {
+            assert!(unknown.parse::<Key>().is_err(), "{unknown:?}");
+        }
+
+        for s in [
+            "j", "c-o", "c-a-x", "return", "space", "f5", "esc", "pageup",
+        ] {
+            assert_eq!(key(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn parses_keymaps() {
+        #[derive(Deserialize)]
+        struct Keys {
+            keys: Keymap,
+        }
+        let keys: Keys = toml::from_str(
+            r#"
+            [keys]
+            down = "j"
+            "c-n" = "j"
+            "c-o" = "o"
+            "#,
+        )
+        .unwrap();
+        let keys = keys.keys;
+        assert_eq!(keys.get(key("down")), Some(key("j")));
+        assert_eq!(keys.get(key("c-o")), Some(key("o")));
+        assert_eq!(keys.get(key("o")), None);
+        assert_eq!(
+            keys.describe("Next: j | Open: o | Close: esc | Plain text"),
+            "Next: c-n/down/j | Open: c-o/o | Close: esc | Plain text"
+        );
+
+        for invalid in [
+            r#"[keys]
+j = "nope""#,
+            r#"[keys]
+"ctrl-j" = "j""#,
+        ] {
+            assert!(toml::from_str::<Keys>(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parses_themes() {
+        let config: Config = toml::from_str(
+            r##"
+            [theme]
+            accent = "magenta"
+            highlight = "#ffaf00"
+            muted = "243"
+            "##,
+        )
+        .unwrap();
+        let theme = config.theme;
+        assert_eq!(theme.accent, Color::Magenta);
+        assert_eq!(theme.highlight, Color::Rgb(0xff, 0xaf, 0x00));
+        assert_eq!(theme.muted, Color::Indexed(243));
+        // Unset colours keep their default
+        assert_eq!(theme.error, Theme::default().error);
+
+        for invalid in [
+            "[theme]\naccent = \"mauve\"",
+            "[theme]\naccent = \"#ff\"",
+            "[theme]\nbackground = \"red\"",
+        ] {
+            assert!(toml::from_str::<Config>(invalid).is_err(), "{invalid}");
+        }
+    }
+}
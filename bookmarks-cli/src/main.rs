@@ -1,10 +1,25 @@
-#![feature(never_type)]
-#![feature(if_let_guard)]
-use std::{fs::File, io, path::PathBuf};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+};
 
-use clap::{Parser, Subcommand, command};
+use bookmarks_data::{
+    BookmarkFile, BookmarkRecord, CommandEmbedder, Embedder, EmbeddingStore, ExportFilter,
+    ExportFormat, HashingEmbedder, Highlights, ImportFormat, IndexedLoad, MatchField, Query,
+};
+use bookmarks_sync::{DirSync, GitSync, MergeStrategy};
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::{WrapErr, eyre};
+use config::{Collection, Config};
+use crossterm::{clipboard::CopyToClipboard, execute};
+use itertools::Itertools;
 use mimalloc::MiMalloc;
+use output::OutputArgs;
 
+mod config;
+mod output;
 mod tui;
 
 #[global_allocator]
@@ -19,24 +34,464 @@ enum Command {
     /// Prints (L) best matched items [default]
     #[command(visible_alias = "f")]
     Find {
-        #[arg(short = 'L', long, default_value = "3")]
-        /// Limits output [0: all]
-        limit: usize,
+        #[arg(short = 'L', long)]
+        /// Limits output [0: all, default: find.limit of the config or 3]
+        limit: Option<usize>,
 
-        /// Needle
+        #[arg(short = 'S', long)]
+        /// Ranks by embedding similarity, see the embed command
+        semantic: bool,
+
+        #[arg(long, value_name = "WEIGHT")]
+        /// Blends similarity (WEIGHT, 0 to 1) with the query score
+        blend: Option<f32>,
+
+        #[command(flatten)]
+        embedder: EmbedderArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Query: fuzzy terms, "phrases", tag:, path:, url:, host:, title:, desc:,
+        /// -negation, OR and parentheses
         search: String,
     },
 
+    /// Opens the url of the best matched item in the browser
+    #[command(visible_alias = "o")]
+    Open {
+        #[command(flatten)]
+        pick: PickArgs,
+    },
+
+    /// Copies the url of the best matched item to the clipboard with OSC 52,
+    /// prints it when stdout is not a terminal
+    #[command(visible_alias = "y")]
+    Copy {
+        #[command(flatten)]
+        pick: PickArgs,
+    },
+
+    /// Computes embeddings for semantic search
+    Embed {
+        #[arg(long)]
+        /// Also recompute existing embeddings
+        force: bool,
+
+        #[arg(long)]
+        /// Keep embeddings in a sidecar file instead of the TOML files, moving existing ones
+        sidecar: bool,
+
+        #[command(flatten)]
+        embedder: EmbedderArgs,
+    },
+
     /// Prints all stored bookmarks
     #[command(visible_alias = "p")]
-    Print,
+    Print {
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Reports files that fail to load, exits with 1 on problems
+    #[command(visible_alias = "lint")]
+    Check,
+
+    /// Creates a new bookmark file
+    #[command(visible_alias = "a")]
+    Add {
+        /// Bookmark url
+        url: String,
+
+        #[arg(short, long)]
+        title: Option<String>,
+
+        #[arg(short = 'T', long = "tag", value_delimiter = ',')]
+        /// Tags, repeated or comma separated
+        tags: Vec<String>,
+
+        #[arg(long)]
+        description: Option<String>,
+
+        #[arg(short, long, default_value = "")]
+        /// Target folder, slash separated
+        folder: String,
+    },
+
+    /// Imports bookmarks from an export or a browser profile, skipping known urls
+    Import {
+        /// bookmarks.html export, Chromium `Bookmarks` or Firefox `places.sqlite`
+        file: PathBuf,
+
+        #[arg(short = 'F', long)]
+        /// Input format [html, chromium, firefox], guessed from the file name by default
+        format: Option<ImportFormat>,
+
+        #[arg(short, long, default_value = "")]
+        /// Target folder for the imported tree, slash separated
+        folder: String,
+    },
+
+    /// Exports bookmarks to stdout or a file
+    Export {
+        #[arg(short = 'F', long, default_value_t = ExportFormat::Html)]
+        /// Output format [html, jsonl, csv]
+        format: ExportFormat,
+
+        #[arg(short, long)]
+        /// Output file [default: stdout]
+        output: Option<PathBuf>,
+
+        #[arg(short, long)]
+        /// Only bookmarks matching the query, same syntax as find
+        search: Option<String>,
+
+        #[arg(short = 'T', long = "tag", value_delimiter = ',')]
+        /// Only bookmarks with all these tags, repeated or comma separated
+        tags: Vec<String>,
+
+        #[arg(short, long, default_value = "")]
+        /// Only bookmarks in this folder, slash separated
+        folder: String,
+    },
+
+    /// Deletes a bookmark file
+    #[command(visible_alias = "rm")]
+    Delete {
+        /// Bookmark file, relative to the data directory
+        file: PathBuf,
+
+        #[arg(short, long)]
+        /// Skip the confirmation prompt
+        yes: bool,
+    },
+
+    /// Moves a bookmark file to another folder
+    #[command(visible_alias = "mv")]
+    Move {
+        /// Bookmark file, relative to the data directory
+        file: PathBuf,
+
+        /// Target folder, slash separated [empty: data root]
+        folder: String,
+    },
+
+    /// Renames a bookmark file inside its folder
+    Rename {
+        /// Bookmark file, relative to the data directory
+        file: PathBuf,
+
+        /// New file name
+        name: String,
+    },
+
+    /// Lists, renames, merges and deletes tags across all bookmarks
+    Tags {
+        #[command(subcommand)]
+        command: Option<TagsCommand>,
+    },
+
+    /// Reports bookmarks sharing an url, ignoring the scheme, `www.`, trailing slashes
+    /// and tracking parameters
+    Dedupe {
+        #[arg(short, long)]
+        /// Merge the duplicates in the interactive interface
+        interactive: bool,
+    },
+
+    /// Commits, pulls with rebase and pushes the data directory git repository
+    Sync {
+        #[arg(short, long, default_value = "Update bookmarks")]
+        /// Commit message for local changes
+        message: String,
+
+        #[arg(long)]
+        /// Do not push after pulling
+        no_push: bool,
+
+        #[arg(short, long)]
+        /// Only print the sync status
+        status: bool,
+
+        #[arg(long, conflicts_with = "status")]
+        /// Abort a rebase stopped on conflicts
+        abort: bool,
+
+        #[arg(long = "continue", conflicts_with_all = ["status", "abort"])]
+        /// Continue a rebase after resolving conflicts
+        resume: bool,
+
+        #[arg(long, default_value_t = MergeStrategy::Markers)]
+        /// Conflicting title and description edits [markers, last-writer-wins]
+        merge: MergeStrategy,
+
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["abort", "resume"])]
+        /// Reconcile with another bookmark directory instead of git
+        dir: Option<PathBuf>,
+
+        #[arg(long, requires = "dir")]
+        /// Print the planned copies and deletes without applying them
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum TagsCommand {
+    /// Prints every tag with the number of bookmarks carrying it [default]
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Renames tags in every file, several tags are merged into one
+    #[command(visible_alias = "mv")]
+    Rename {
+        #[arg(required = true)]
+        /// Tags to rename, compared exactly
+        tags: Vec<String>,
+
+        #[arg(short, long)]
+        /// New tag
+        to: String,
+    },
+
+    /// Removes tags from every file
+    #[command(visible_alias = "rm")]
+    Delete {
+        #[arg(required = true)]
+        /// Tags to remove, compared exactly
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct PickArgs {
+    #[arg(short, long)]
+    /// Take the best match without asking, also when stdin is not a terminal
+    first: bool,
+
+    /// Query, same syntax as find
+    search: String,
+}
+
+/// Matches scoring at least this share of the best one are offered as well
+const CLOSE_SCORE: f64 = 0.9;
+
+/// The best of the ranked `(index, score)` pairs followed by the ones scoring close to it,
+/// at most 9. Empty without matches.
+fn close_matches(ranked: impl IntoIterator<Item = (usize, i64)>) -> Vec<usize> {
+    let mut ranked = ranked.into_iter();
+    let Some((best, best_score)) = ranked.next() else {
+        return vec![];
+    };
+    std::iter::once(best)
+        .chain(
+            ranked
+                .take_while(|(_, score)| *score as f64 >= best_score as f64 * CLOSE_SCORE)
+                .map(|(i, _)| i),
+        )
+        .take(9)
+        .collect()
+}
+
+impl PickArgs {
+    /// Best match of the query, asks which one when others score close to it
+    fn pick(self, files: &[BookmarkFile]) -> color_eyre::Result<&BookmarkFile> {
+        let found = bookmarks_data::search(&self.search, files.iter().map(|it| &it.content));
+        let close = close_matches(found.map(|(i, it)| (i, it.score)));
+        let Some(best) = close.first().copied() else {
+            return Err(eyre!("No bookmark matches {:?}", self.search));
+        };
+        if self.first || close.len() == 1 || !io::stdin().is_terminal() {
+            return Ok(&files[best]);
+        }
+        for (n, i) in close.iter().enumerate() {
+            let record = &files[*i].content;
+            eprintln!(
+                "{}) {} {}",
+                n + 1,
+                record.title.as_deref().unwrap_or_default(),
+                record.url
+            );
+        }
+        eprint!("Which one? [1-{}, default 1] ", close.len());
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        let n: usize = match answer.trim() {
+            "" => 1,
+            answer => answer.parse().unwrap_or(0),
+        };
+        match close.get(n.wrapping_sub(1)) {
+            Some(i) => Ok(&files[*i]),
+            None => Err(eyre!("No bookmark chosen")),
+        }
+    }
+}
+
+/// The data directory of the only collection, commands changing files need one
+/// picked with `--collection` when there are several
+fn single(collections: &[Collection]) -> color_eyre::Result<&Path> {
+    match collections {
+        [collection] => Ok(&collection.path),
+        _ => Err(eyre!(
+            "Several collections ({}), pick one with --collection",
+            collections.iter().map(|it| &it.name).join(", ")
+        )),
+    }
+}
+
+/// Files of every collection, in their order
+fn load_all(collections: &[Collection]) -> color_eyre::Result<Vec<BookmarkFile>> {
+    let mut files = vec![];
+    for collection in collections {
+        files.extend(
+            bookmarks_data::load_from_fs(&collection.path)?.map(|mut file| {
+                file.content.collection = Some(collection.name.clone());
+                file
+            }),
+        );
+    }
+    Ok(files)
+}
+
+/// [`bookmarks_data::load_indexed`] of every collection, in their order
+fn load_all_indexed(collections: &[Collection]) -> color_eyre::Result<IndexedLoad> {
+    let mut load = IndexedLoad::default();
+    for collection in collections {
+        let mut next = bookmarks_data::load_indexed(&collection.path)?;
+        for indexed in &mut next.files {
+            indexed.file.content.collection = Some(collection.name.clone());
+        }
+        load.files.append(&mut next.files);
+        load.diagnostics.append(&mut next.diagnostics);
+    }
+    Ok(load)
+}
+
+/// Path of the file in its data directory, behind the collection name when `qualified`
+fn file_label(file: &BookmarkFile, qualified: bool) -> String {
+    match (&file.content.collection, qualified) {
+        (Some(collection), true) => {
+            format!("{collection}:{}", file.relative_path.to_string_lossy())
+        }
+        _ => file.relative_path.to_string_lossy().to_string(),
+    }
+}
+
+/// Number of results find prints: `--limit`, else the config, else 3, where 0 means all
+fn find_limit(limit: Option<usize>, config: &Config) -> usize {
+    match limit.or(config.find.limit).unwrap_or(3) {
+        0 => usize::MAX,
+        limit => limit,
+    }
+}
+
+fn split_folder(folder: &str) -> Vec<String> {
+    folder
+        .split('/')
+        .filter(|it| !it.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `text` with the characters at `indices` in bold yellow, plain when not `colored`
+fn highlight(text: &str, indices: &[usize], colored: bool) -> String {
+    use crossterm::style::Stylize;
+
+    if !colored || indices.is_empty() {
+        return text.to_string();
+    }
+    text.chars()
+        .enumerate()
+        .map(|(i, ch)| match indices.binary_search(&i) {
+            Ok(_) => ch.yellow().bold().to_string(),
+            Err(_) => ch.to_string(),
+        })
+        .collect()
+}
+
+/// Prints one search result, a field per line
+fn print_found(file: &BookmarkFile, header: &str, highlights: &Highlights, qualified: bool) {
+    let colored = io::stdout().is_terminal();
+    let record = &file.content;
+    println!("--- ({header}) {}", file_label(file, qualified));
+    if let Some(title) = &record.title {
+        println!(
+            "{}",
+            highlight(title, highlights.get(MatchField::Title), colored)
+        );
+    }
+    println!(
+        "{}",
+        highlight(&record.url, highlights.get(MatchField::Url), colored)
+    );
+    if let Some(tags) = record.tags.as_ref().filter(|it| !it.is_empty()) {
+        let tags = tags
+            .iter()
+            .enumerate()
+            .map(|(i, tag)| highlight(tag, highlights.get(MatchField::Tag(i)), colored))
+            .join(", ");
+        println!("Tags: {tags}");
+    }
+    if !record.path.is_empty() {
+        let folder = record
+            .path
+            .iter()
+            .enumerate()
+            .map(|(i, part)| highlight(part, highlights.get(MatchField::Path(i)), colored))
+            .join("/");
+        println!("Folder: {folder}");
+    }
+    if let Some(description) = &record.description {
+        println!(
+            "{}",
+            highlight(
+                description,
+                highlights.get(MatchField::Description),
+                colored
+            )
+        );
+    }
+}
+
+#[derive(Debug, clap::Args)]
+struct EmbedderArgs {
+    #[arg(long, default_value_t = HashingEmbedder::default().dimensions)]
+    /// Dimensions of the built in hashing embedder
+    dimensions: usize,
+
+    #[arg(long, value_name = "PROGRAM")]
+    /// Local embedder program: reads text on stdin, prints a JSON array of numbers
+    embed_command: Option<PathBuf>,
+}
+
+impl EmbedderArgs {
+    fn embedder(&self) -> Box<dyn Embedder> {
+        match &self.embed_command {
+            Some(program) => Box::new(CommandEmbedder {
+                program: program.clone(),
+                args: vec![],
+            }),
+            None => Box::new(HashingEmbedder {
+                dimensions: self.dimensions,
+            }),
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    #[arg(short, long, global = true, default_value = "./sample-data")]
-    data: PathBuf,
+    #[arg(short, long, global = true, value_name = "[NAME=]DIR")]
+    /// Data directory, repeat for more collections [default: collections of the config or ./sample-data]
+    data: Vec<Collection>,
+
+    #[arg(long, global = true, value_name = "NAME")]
+    /// Only work on this collection [default: every one, commands changing files need
+    /// it when there are several]
+    collection: Option<String>,
+
+    #[arg(long, global = true, value_name = "FILE")]
+    /// Config file [default: $XDG_CONFIG_HOME/bookmarks/config.toml]
+    config: Option<PathBuf>,
 
     #[command(subcommand)]
     command: Option<Command>,
@@ -48,10 +503,29 @@ struct Args {
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
     let args = Args::parse();
-    if matches!(args.command, Some(Command::Tui) | None) {
+    let config = Config::load(args.config.as_deref())?;
+    let mut collections = config.collections(&args.data)?;
+    if let Some(name) = &args.collection {
+        collections.retain(|it| it.name == *name);
+        if collections.is_empty() {
+            return Err(eyre!("Unknown collection {name:?}"));
+        }
+    }
+    let qualified = collections.len() > 1;
+    if matches!(
+        args.command,
+        Some(Command::Tui | Command::Dedupe { interactive: true }) | None
+    ) {
+        let log = config
+            .log
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("./log.jsonl"));
+        if let Some(parent) = log.parent().filter(|it| !it.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
         tracing_subscriber::fmt()
             .with_max_level(args.verbosity)
-            .with_writer(File::create("./log.jsonl")?)
+            .with_writer(File::create(log)?)
             .init();
     } else {
         tracing_subscriber::fmt()
@@ -59,37 +533,440 @@ fn main() -> color_eyre::Result<()> {
             .with_max_level(args.verbosity)
             .init();
     }
-    tracing::debug!("{args:?}");
+    tracing::debug!("{args:?} {config:?}");
 
     match args.command {
-        Some(Command::Find { search, limit }) => {
-            let data = bookmarks_data::load_from_fs(args.data)?
+        Some(Command::Find {
+            search,
+            limit,
+            semantic,
+            blend,
+            embedder,
+            output,
+        }) if semantic || blend.is_some() => {
+            let limit = find_limit(limit, &config);
+            let files = load_all(&collections)?;
+            let vector = embedder.embedder().embed(&search)?;
+            let res = match blend {
+                Some(weight) => {
+                    let records: Vec<_> = files.iter().map(|it| &it.content).collect();
+                    bookmarks_data::hybrid_search(&Query::parse(&search), &vector, &records, weight)
+                }
+                None => {
+                    bookmarks_data::semantic_search(&vector, files.iter().map(|it| &it.content))
+                }
+            };
+            if files.iter().all(|it| it.content.embeddings.is_none()) {
+                eprintln!("No embeddings stored, run the embed command first");
+            }
+            let res = res.into_iter().take(limit);
+            match output.printer() {
+                Some(mut printer) => {
+                    for (i, similarity) in res {
+                        printer.print(&files[i], Some(similarity.into()))?;
+                    }
+                    printer.finish()?;
+                }
+                None => {
+                    for (i, similarity) in res {
+                        print_found(
+                            &files[i],
+                            &format!("similarity {similarity:.3}"),
+                            &Highlights::default(),
+                            qualified,
+                        );
+                    }
+                }
+            }
+        }
+        Some(Command::Find {
+            search,
+            limit,
+            output,
+            ..
+        }) => {
+            let limit = find_limit(limit, &config);
+            let load = load_all_indexed(&collections)?;
+            for diagnostic in &load.diagnostics {
+                tracing::warn!("{diagnostic}");
+            }
+            let (files, index) = load.into_search();
+            let res = index
+                .search(&Query::parse(&search), |i| &files[i].content)
+                .into_iter()
+                .take(limit);
+            match output.printer() {
+                Some(mut printer) => {
+                    for (i, found) in res {
+                        printer.print(&files[i], Some(found.score.into()))?;
+                    }
+                    printer.finish()?;
+                }
+                None => {
+                    for (i, found) in res {
+                        print_found(
+                            &files[i],
+                            &format!("score {}", found.score),
+                            &found.highlights,
+                            qualified,
+                        );
+                    }
+                }
+            }
+        }
+        Some(Command::Open { pick }) => {
+            let files = load_all(&collections)?;
+            let file = pick.pick(&files)?;
+            open::that(&file.content.url)
+                .wrap_err_with(|| format!("Opening {} failed", file.content.url))?;
+        }
+        Some(Command::Copy { pick }) => {
+            let files = load_all(&collections)?;
+            let file = pick.pick(&files)?;
+            if io::stdout().is_terminal() {
+                execute!(
+                    io::stdout(),
+                    CopyToClipboard::to_clipboard_from(&file.content.url)
+                )?;
+                eprintln!("Copied {}", file.content.url);
+            } else {
+                println!("{}", file.content.url);
+            }
+        }
+        Some(Command::Embed {
+            force,
+            sidecar,
+            embedder,
+        }) => {
+            let embedder = embedder.embedder();
+            let (mut count, mut moved) = (0, 0);
+            for collection in &collections {
+                let data = &collection.path;
+                let mut store = match sidecar {
+                    true => Some(EmbeddingStore::open_or_create(data)?),
+                    false => EmbeddingStore::open(data)?,
+                };
+                let mut paths = HashSet::new();
+                for mut file in bookmarks_data::load_from_fs(data)? {
+                    paths.insert(file.relative_path.clone());
+                    let inline = file.content.embeddings.is_some()
+                        && store.as_ref().is_some_and(|it| it.get(&file).is_none());
+                    if file.content.embeddings.is_some() && !force {
+                        if inline && let Some(store) = store.as_mut() {
+                            let vector = file.content.embeddings.take().unwrap_or_default();
+                            store.insert(&file, vector);
+                            bookmarks_data::save_to_fs(&file)?;
+                            moved += 1;
+                        }
+                        continue;
+                    }
+                    let vector = embedder.embed(&bookmarks_data::embedding_text(&file.content))?;
+                    match store.as_mut() {
+                        Some(store) => {
+                            store.insert(&file, vector);
+                            if inline {
+                                file.content.embeddings = None;
+                                bookmarks_data::save_to_fs(&file)?;
+                            }
+                        }
+                        None => {
+                            file.content.embeddings = Some(vector);
+                            bookmarks_data::save_to_fs(&file)?;
+                        }
+                    }
+                    count += 1;
+                }
+                if let Some(store) = store.as_mut() {
+                    store.retain(|path| paths.contains(path));
+                    store.save()?;
+                }
+            }
+            eprintln!("Embedded {count} bookmark(s), moved {moved} to the sidecar file");
+        }
+        Some(Command::Print { output }) => {
+            let files = load_all(&collections)?;
+            match output.printer() {
+                Some(mut printer) => {
+                    for file in files {
+                        printer.print(&file, None)?;
+                    }
+                    printer.finish()?;
+                }
+                None => {
+                    for file in files {
+                        println!(
+                            "{}: {}",
+                            file.path.to_string_lossy(),
+                            file.content.fuzzy_string()
+                        );
+                    }
+                }
+            }
+        }
+        Some(Command::Tags { command }) => match command.unwrap_or(TagsCommand::List) {
+            TagsCommand::List => {
+                let files = load_all(&collections)?;
+                for (tag, count) in bookmarks_data::tag_counts(files.iter().map(|it| &it.content)) {
+                    println!("{count:>6} {tag}");
+                }
+            }
+            TagsCommand::Rename { tags, to } => {
+                let mut count = 0;
+                for collection in &collections {
+                    count += bookmarks_data::rename_tags(&collection.path, &tags, &to)?;
+                }
+                eprintln!("Changed {count} file(s)");
+            }
+            TagsCommand::Delete { tags } => {
+                let mut count = 0;
+                for collection in &collections {
+                    count += bookmarks_data::delete_tags(&collection.path, &tags)?;
+                }
+                eprintln!("Changed {count} file(s)");
+            }
+        },
+        Some(Command::Dedupe { interactive: true }) => {
+            tui::run(collections, config, true)?;
+        }
+        Some(Command::Dedupe { interactive: false }) => {
+            let files = load_all(&collections)?;
+            let groups = bookmarks_data::find_duplicates(files.iter().map(|it| &it.content));
+            for group in &groups {
+                println!("{} ({})", group.url, group.records.len());
+                for i in &group.records {
+                    println!("  {}", file_label(&files[*i], qualified));
+                }
+            }
+            let count: usize = groups.iter().map(|it| it.records.len() - 1).sum();
+            eprintln!(
+                "{count} duplicate(s) in {} group(s), merge them with dedupe --interactive",
+                groups.len()
+            );
+        }
+        Some(Command::Check) => {
+            let mut diagnostics = vec![];
+            for collection in &collections {
+                diagnostics.extend(bookmarks_data::check(&collection.path)?);
+            }
+            for diagnostic in &diagnostics {
+                println!("{diagnostic}");
+            }
+            if !diagnostics.is_empty() {
+                eprintln!("{} problem(s) found", diagnostics.len());
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Add {
+            url,
+            title,
+            tags,
+            description,
+            folder,
+        }) => {
+            let tags: Vec<String> = tags
+                .iter()
+                .map(|it| it.trim())
+                .filter(|it| !it.is_empty())
+                .map(str::to_string)
+                .unique()
+                .collect();
+            let record = BookmarkRecord {
+                path: split_folder(&folder),
+                title,
+                url,
+                tags: (!tags.is_empty()).then_some(tags),
+                description,
+                embeddings: None,
+                collection: None,
+            };
+            let file = bookmarks_data::create_in_fs(single(&collections)?, record)?;
+            println!("{}", file.path.to_string_lossy());
+        }
+        Some(Command::Import {
+            file,
+            format,
+            folder,
+        }) => {
+            let data = single(&collections)?;
+            let format = format
+                .or_else(|| ImportFormat::detect(&file))
+                .ok_or_else(|| eyre!("Unknown format of {file:?}, pass it with --format"))?;
+            let prefix = split_folder(&folder);
+            let records =
+                bookmarks_data::read_import(&file, format)?
+                    .into_iter()
+                    .map(|mut record| {
+                        record.path.splice(0..0, prefix.iter().cloned());
+                        record
+                    });
+            let report = bookmarks_data::import_records(data, records)?;
+            for file in &report.created {
+                println!("{}", file.path.to_string_lossy());
+            }
+            for record in &report.invalid {
+                eprintln!("invalid url: {:?}", record.url);
+            }
+            eprintln!(
+                "Imported {}, skipped {} duplicate(s) and {} invalid",
+                report.created.len(),
+                report.duplicates.len(),
+                report.invalid.len()
+            );
+        }
+        Some(Command::Export {
+            format,
+            output,
+            search,
+            tags,
+            folder,
+        }) => {
+            let filter = ExportFilter {
+                query: search.as_deref().map(Query::parse),
+                tags,
+                folder: split_folder(&folder),
+            };
+            let records = load_all(&collections)?
+                .into_iter()
                 .map(|it| it.content)
-                .collect::<Vec<_>>();
-            let res = bookmarks_data::search(&search, data.iter());
-            let res = match limit {
-                0 => res.take(usize::MAX),
-                1.. => res.take(limit),
+                .filter(|it| filter.matches(it));
+            let count = match output {
+                Some(path) => bookmarks_data::export(
+                    format,
+                    records,
+                    io::BufWriter::new(File::create(path)?),
+                )?,
+                None => bookmarks_data::export(format, records, io::stdout().lock())?,
             };
-            for (i, it) in res {
-                let content = &data[i];
-                println!("--- (score {it})");
-                println!("{content:?}");
-                println!("{}", content.fuzzy_string());
+            eprintln!("Exported {count} bookmark(s)");
+        }
+        Some(Command::Delete { file, yes }) => {
+            let file = bookmarks_data::load_file(single(&collections)?, file)?;
+            if !yes {
+                eprint!("Delete {}? [y/N] ", file.path.to_string_lossy());
+                let mut answer = String::new();
+                io::stdin().read_line(&mut answer)?;
+                if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                    return Ok(());
+                }
             }
+            bookmarks_data::delete_from_fs(&file)?;
+        }
+        Some(Command::Move { file, folder }) => {
+            let mut file = bookmarks_data::load_file(single(&collections)?, file)?;
+            bookmarks_data::move_in_fs(&mut file, &split_folder(&folder))?;
+            println!("{}", file.path.to_string_lossy());
         }
-        Some(Command::Print) => {
-            for file in bookmarks_data::load_from_fs(args.data)? {
-                println!(
-                    "{}: {}",
-                    file.path.to_string_lossy(),
-                    file.content.fuzzy_string()
-                );
+        Some(Command::Rename { file, name }) => {
+            let mut file = bookmarks_data::load_file(single(&collections)?, file)?;
+            bookmarks_data::rename_in_fs(&mut file, &name)?;
+            println!("{}", file.path.to_string_lossy());
+        }
+        Some(Command::Sync {
+            status,
+            dir: Some(dir),
+            dry_run,
+            ..
+        }) => {
+            let dir_sync = DirSync::new(single(&collections)?, dir);
+            let plan = dir_sync.plan()?;
+            for action in &plan.actions {
+                println!("{action}");
+            }
+            for path in &plan.conflicts {
+                println!("conflict: {} (newest copy wins)", path.to_string_lossy());
+            }
+            if plan.actions.is_empty() {
+                println!("up to date");
+            }
+            if !dry_run && !status {
+                dir_sync.apply(&plan)?;
             }
         }
+        Some(Command::Sync {
+            message,
+            no_push,
+            status,
+            abort,
+            resume,
+            merge,
+            dir: None,
+            ..
+        }) => {
+            let git = GitSync::open(single(&collections)?)?;
+            let report = if abort {
+                git.abort_rebase()?;
+                None
+            } else if resume {
+                Some(git.resume(!no_push, merge)?)
+            } else if !status {
+                Some(git.sync(&message, !no_push, merge)?)
+            } else {
+                None
+            };
+            if let Some(report) = report {
+                tracing::debug!("{report:?}");
+                for path in &report.merged {
+                    println!("merged: {}", path.to_string_lossy());
+                }
+                if !report.conflicts.is_empty() {
+                    for conflict in &report.conflicts {
+                        println!("conflict: {}", conflict.path.to_string_lossy());
+                        for field in &conflict.fields {
+                            println!(
+                                "  {}: {:?} (local) / {:?} (remote)",
+                                field.field,
+                                field.local.as_deref().unwrap_or_default(),
+                                field.remote.as_deref().unwrap_or_default()
+                            );
+                        }
+                    }
+                    return Err(eyre!(
+                        "Sync stopped on conflicts, resolve them and run sync --continue"
+                    ));
+                }
+            }
+            let status = git.status()?;
+            for path in &status.conflicts {
+                println!("conflict: {}", path.to_string_lossy());
+            }
+            println!("{status}");
+        }
         Some(Command::Tui) | None => {
-            tui::run(args.data)?;
+            tui::run(collections, config, false)?;
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_matches_offer_near_ties() {
+        assert_eq!(close_matches([]), Vec::<usize>::new());
+        assert_eq!(close_matches([(4, 100)]), [4]);
+        // Far behind the best one
+        assert_eq!(close_matches([(2, 100), (0, 89), (1, 88)]), [2]);
+        // Ties and scores within 90% of the best one, in their order
+        assert_eq!(
+            close_matches([(2, 100), (0, 100), (1, 90), (3, 50)]),
+            [2, 0, 1]
+        );
+        assert_eq!(
+            close_matches((0..20).map(|i| (i, 100))),
+            (0..9).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn find_limit_falls_back_to_the_config() {
+        let mut config = Config::default();
+        assert_eq!(find_limit(None, &config), 3);
+        assert_eq!(find_limit(Some(5), &config), 5);
+        assert_eq!(find_limit(Some(0), &config), usize::MAX);
+        config.find.limit = Some(0);
+        assert_eq!(find_limit(None, &config), usize::MAX);
+        assert_eq!(find_limit(Some(2), &config), 2);
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use bookmarks_data::{BookmarkFile, BookmarkRecord};
+use color_eyre::eyre::eyre;
+use itertools::Itertools;
+use serde_json::{Map, Value};
+use strum::{Display, EnumString};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum OutputFormat {
+    /// One array of objects
+    Json,
+    /// One object per line
+    Jsonl,
+    /// Tab separated fields, one bookmark per line, no header
+    Tsv,
+    /// A `[[bookmark]]` table per bookmark
+    Toml,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct OutputArgs {
+    #[arg(long, value_name = "FORMAT")]
+    /// Machine readable output [json, jsonl, tsv, toml]
+    format: Option<OutputFormat>,
+
+    #[arg(long, conflicts_with = "format")]
+    /// Prints every bookmark as TEMPLATE, e.g. "{title}\t{url}".
+    /// Fields: file, folder, title, url, tags, description, score (find only)
+    template: Option<Template>,
+}
+
+impl OutputArgs {
+    /// `None` for the human readable output
+    pub fn printer(&self) -> Option<Printer> {
+        let style = match (self.format, &self.template) {
+            (_, Some(template)) => Style::Template(template.clone()),
+            (Some(format), None) => Style::Format(format),
+            (None, None) => return None,
+        };
+        Some(Printer::new(style, io::stdout().lock()))
+    }
+}
+
+/// Fields of a printed bookmark: the file, its folder, the serialized [`BookmarkRecord`]
+/// without embeddings and the search score, in this order
+fn fields(file: &BookmarkFile, score: Option<Value>) -> Map<String, Value> {
+    let mut fields = Map::new();
+    fields.insert("file".into(), file.path.to_string_lossy().into());
+    fields.insert("folder".into(), file.content.path.join("/").into());
+    if let Ok(Value::Object(record)) = serde_json::to_value(&file.content) {
+        fields.extend(record.into_iter().filter(|(name, _)| name != "embeddings"));
+    }
+    if let Some(score) = score {
+        fields.insert("score".into(), score);
+    }
+    fields
+}
+
+fn is_field(name: &str) -> bool {
+    let file = BookmarkFile {
+        content: BookmarkRecord::default(),
+        path: PathBuf::new(),
+        relative_path: PathBuf::new(),
+    };
+    fields(&file, Some(Value::Null)).contains_key(name)
+}
+
+/// Plain text of a field, lists are comma separated and missing values empty
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(text).join(","),
+        value => value.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(String),
+}
+
+/// `{field}` placeholders between text, with `\t`, `\n`, `\\`, `{{` and `}}` escapes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template(Vec<Part>);
+
+impl FromStr for Template {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                '{' if chars.next_if_eq(&'{').is_some() => literal.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => literal.push('}'),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(ch) => name.push(ch),
+                            None => return Err(eyre!("Unclosed {{{name} in template")),
+                        }
+                    }
+                    if !is_field(&name) {
+                        return Err(eyre!("Unknown field {{{name}}} in template"));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(name));
+                }
+                '}' => return Err(eyre!("Unmatched }} in template, write }}}} for a brace")),
+                ch => literal.push(ch),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Text(literal));
+        }
+        Ok(Self(parts))
+    }
+}
+
+impl Template {
+    fn render(&self, fields: &Map<String, Value>) -> String {
+        self.0
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Field(name) => fields.get(name).map(text).unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+enum Style {
+    Format(OutputFormat),
+    Template(Template),
+}
+
+/// Writes bookmarks to stdout in the chosen format, stops quietly once the reader is gone
+pub struct Printer<W = io::StdoutLock<'static>> {
+    style: Style,
+    out: W,
+    /// Formats written as a whole by [`Printer::finish`]
+    collected: Vec<Map<String, Value>>,
+    closed: bool,
+}
+
+impl<W: Write> Printer<W> {
+    fn new(style: Style, out: W) -> Self {
+        Self {
+            style,
+            out,
+            collected: vec![],
+            closed: false,
+        }
+    }
+
+    pub fn print(&mut self, file: &BookmarkFile, score: Option<Value>) -> io::Result<()> {
+        let fields = fields(file, score);
+        let line = match &self.style {
+            Style::Template(template) => template.render(&fields),
+            Style::Format(OutputFormat::Jsonl) => serde_json::to_string(&fields)?,
+            Style::Format(OutputFormat::Tsv) => fields
+                .values()
+                .map(|it| text(it).replace(['\t', '\n', '\r'], " "))
+                .join("\t"),
+            Style::Format(OutputFormat::Json | OutputFormat::Toml) => {
+                self.collected.push(fields);
+                return Ok(());
+            }
+        };
+        self.write(|out| writeln!(out, "{line}"))
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        let collected = std::mem::take(&mut self.collected);
+        let content = match self.style {
+            Style::Format(OutputFormat::Json) => serde_json::to_string_pretty(&collected)?,
+            Style::Format(OutputFormat::Toml) => {
+                // TOML has no null
+                let bookmarks: Vec<_> = collected
+                    .into_iter()
+                    .map(|mut it| {
+                        it.retain(|_, value| !value.is_null());
+                        it
+                    })
+                    .collect();
+                let mut document = Map::new();
+                document.insert("bookmark".into(), bookmarks.into());
+                toml::to_string_pretty(&document).map_err(io::Error::other)?
+            }
+            _ => return self.write(|out| out.flush()),
+        };
+        self.write(|out| {
+            writeln!(out, "{}", content.trim_end())?;
+            out.flush()
+        })
+    }
+
+    fn write(&mut self, write: impl FnOnce(&mut W) -> io::Result<()>) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        match write(&mut self.out) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(s: &str) -> Template {
+        s.parse().unwrap()
+    }
+
+    fn file() -> BookmarkFile {
+        BookmarkFile {
+            content: BookmarkRecord {
+                path: vec!["dev".into(), "rust".into()],
+                title: Some("Rust\tbook".into()),
+                url: "https://doc.rust-lang.org/book".into(),
+                tags: Some(vec!["rust".into(), "docs".into()]),
+                description: None,
+                embeddings: Some(vec![1.0]),
+                collection: None,
+            },
+            path: PathBuf::from("/data/dev/rust/book.toml"),
+            relative_path: PathBuf::from("dev/rust/book.toml"),
+        }
+    }
+
+    fn print(style: Style, score: Option<Value>) -> String {
+        let mut out = vec![];
+        let mut printer = Printer::new(style, &mut out);
+        printer.print(&file(), score.clone()).unwrap();
+        printer.print(&file(), score).unwrap();
+        printer.finish().unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parses_templates() {
+        use Part::*;
+
+        assert_eq!(
+            template("{title}\\t{url}\\n"),
+            Template(vec![
+                Field("title".into()),
+                Text("\t".into()),
+                Field("url".into()),
+                Text("\n".into()),
+            ])
+        );
+        assert_eq!(
+            template("{{{tags}}} \\\\ \\x \\"),
+            Template(vec![
+                Text("{".into()),
+                Field("tags".into()),
+                Text("} \\ \\x \\".into()),
+            ])
+        );
+        assert_eq!(template(""), Template(vec![]));
+        assert_eq!(template("{score}"), Template(vec![Field("score".into())]));
+
+        for (invalid, message) in [
+            ("{nope}", "Unknown field {nope} in template"),
+            ("{embeddings}", "Unknown field {embeddings} in template"),
+            ("{title", "Unclosed {title in template"),
+            ("{title}}", "Unmatched } in template, write }} for a brace"),
+        ] {
+            let err = invalid.parse::<Template>().unwrap_err();
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn renders_templates() {
+        let fields = fields(&file(), None);
+        assert_eq!(
+            template("{folder}: {title} [{tags}] {description}{score}").render(&fields),
+            "dev/rust: Rust\tbook [rust,docs] "
+        );
+    }
+
+    #[test]
+    fn prints_formats() {
+        assert_eq!(
+            print(Style::Format(OutputFormat::Tsv), Some(7.into())),
+            "/data/dev/rust/book.toml\tdev/rust\tRust book\thttps://doc.rust-lang.org/book\trust,docs\t\t7\n"
+                .repeat(2)
+        );
+
+        let jsonl = print(Style::Format(OutputFormat::Jsonl), None);
+        let lines: Vec<Value> = jsonl
+            .lines()
+            .map(|it| serde_json::from_str(it).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["folder"], "dev/rust");
+        assert_eq!(lines[0]["tags"], serde_json::json!(["rust", "docs"]));
+        assert!(lines[0].get("embeddings").is_none());
+
+        let json: Value =
+            serde_json::from_str(&print(Style::Format(OutputFormat::Json), Some(0.5.into())))
+                .unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 2);
+        assert_eq!(json[1]["score"], 0.5);
+        assert_eq!(json[1]["description"], Value::Null);
+
+        let toml: toml::Table = print(Style::Format(OutputFormat::Toml), None)
+            .parse()
+            .unwrap();
+        let bookmarks = toml["bookmark"].as_array().unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0]["title"].as_str(), Some("Rust\tbook"));
+        // Missing values are left out
+        assert!(bookmarks[0].get("description").is_none());
+
+        assert_eq!(
+            print(Style::Template(template("{url}")), None),
+            "https://doc.rust-lang.org/book\n".repeat(2)
+        );
+    }
+}
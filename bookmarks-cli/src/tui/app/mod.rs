@@ -1,22 +1,28 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
+use bookmarks_data::{HashingEmbedder, IndexedFile};
 use futures::FutureExt;
 use ratatui::DefaultTerminal;
-use tokio::{select, sync::mpsc};
+use tokio::{process::Command, select, sync::mpsc};
 
 mod stack;
 mod state;
 mod view;
 
-use crate::tui::{
-    app::{
-        stack::AppStack,
-        state::AppState,
-        view::{error::ErrorView, main::MainView},
+use crate::{
+    config::{Collection, Config},
+    tui::{
+        app::{
+            stack::AppStack,
+            state::AppState,
+            view::{duplicates::DuplicatesView, error::ErrorView, main::MainView},
+        },
+        data::{DataEvent, DataWorker},
+        event::AppEvent,
+        search::{SearchResults, SearchWorker},
+        sync::{SyncEvent, SyncWorker},
+        terminal_events::TerminalPoller,
     },
-    data::{DataEvent, DataWorker},
-    event::AppEvent,
-    terminal_events::TerminalPoller,
 };
 
 pub struct App {
@@ -37,7 +43,58 @@ impl Drop for App {
 }
 
 impl App {
-    pub async fn run(mut self, data: PathBuf) -> color_eyre::Result<()> {
+    /// Suspends the interface while `$EDITOR` runs on `path`, then reloads the file
+    async fn open_in_editor(
+        &mut self,
+        terminal_poller: &mut TerminalPoller,
+        state: &mut AppState,
+        stack: &mut AppStack,
+        path: PathBuf,
+    ) -> color_eyre::Result<()> {
+        // The editor owns stdin until it exits
+        terminal_poller.stop().await;
+        ratatui::restore();
+        let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+        let mut args = editor.split_whitespace();
+        let status = Command::new(args.next().unwrap_or("vi"))
+            .args(args)
+            .arg(&path)
+            .status()
+            .await;
+        self.terminal = ratatui::try_init()?;
+        self.terminal.clear()?;
+        terminal_poller.init_poller();
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                stack.push(Box::new(ErrorView::new(format!(
+                    "{editor} exited with {status}"
+                ))));
+                return Ok(());
+            }
+            Err(e) => {
+                stack.push(Box::new(ErrorView::new(format!(
+                    "Failed to run {editor}: {e}"
+                ))));
+                return Ok(());
+            }
+        }
+        match state.load_file(&path) {
+            Ok(file) => state.reload_item(file),
+            Err(e) => stack.push(Box::new(
+                ErrorView::new(format!("{}: {e}", path.to_string_lossy())).reopen(path),
+            )),
+        }
+        Ok(())
+    }
+
+    pub async fn run(
+        mut self,
+        collections: Vec<Collection>,
+        config: Config,
+        duplicates: bool,
+    ) -> color_eyre::Result<()> {
         let tick_rate = std::time::Duration::from_millis(250);
         let mut interval = tokio::time::interval(tick_rate);
         let (e_tx, mut e_rx) = mpsc::unbounded_channel::<AppEvent>();
@@ -45,29 +102,83 @@ impl App {
         terminal_poller.init_poller();
         let (d_tx, mut d_rx) = mpsc::unbounded_channel::<DataEvent>();
         let mut data_worker = DataWorker::new(d_tx);
-        data_worker.load_items(data);
+        data_worker.load_items(collections.clone());
+        let data = collections[0].path.clone();
+        let (s_tx, mut s_rx) = mpsc::unbounded_channel::<SyncEvent>();
+        let mut sync_worker = SyncWorker::new(s_tx);
+        sync_worker.watch_status(data.clone());
+        let (r_tx, mut r_rx) = mpsc::unbounded_channel::<SearchResults>();
+        let mut search_worker = SearchWorker::new(r_tx);
+        let searcher = search_worker.start(Some(Arc::new(HashingEmbedder::default())));
 
-        let mut state = AppState::default();
+        let mut state = AppState {
+            data,
+            collections,
+            searcher: Some(searcher),
+            keys: config.keys,
+            theme: config.theme,
+            ..Default::default()
+        };
         let mut stack = AppStack::default();
         stack.push(Box::new(MainView::default()));
+        if duplicates {
+            stack.push(Box::new(DuplicatesView::default()));
+        }
         while !stack.should_quit {
             let maybe_event = select! {
             maybe_event = d_rx.recv().fuse() => {
-                if let Some(evt) = maybe_event  {
+                // Takes everything already queued before drawing again
+                let mut next = maybe_event;
+                while let Some(evt) = next {
                     match evt {
                         DataEvent::NewFile(file) => {
-                            state.items.push(file);
+                            let IndexedFile { file, tokens } = *file;
+                            state.push_item(file, Some(tokens));
                         }
-                        DataEvent::Loaded => {
-                            state.items_loaded = true;
+                        DataEvent::Diagnostic(diagnostic) => {
+                            tracing::warn!("{diagnostic}");
+                            state.diagnostics.retain(|it| it.path != diagnostic.path);
+                            state.diagnostics.push(diagnostic);
                         }
-                        DataEvent::LoadError(err) => {
-                            stack.push_block(Box::new(ErrorView(err.to_string())))
+                        DataEvent::Loaded => state.collection_loaded(),
+                        DataEvent::LoadError(name, err) => {
+                            state.collection_loaded();
+                            let message = match state.collections.len() {
+                                1 => err.to_string(),
+                                _ => format!("{name}: {err}"),
+                            };
+                            stack.push_block(Box::new(ErrorView::new(message)))
+                        }
+                        DataEvent::FileAdded(file) | DataEvent::FileChanged(file) => {
+                            tracing::debug!("Reloading {:?}", file.path);
+                            state.reload_item(*file);
+                        }
+                        DataEvent::FileRemoved(path) => {
+                            state.diagnostics.retain(|it| it.path != path);
+                            if let Some(index) = state.position_of(&path) {
+                                state.remove_item(index);
+                            }
                         }
                     }
+                    next = d_rx.try_recv().ok();
                 }
                 None
             }
+            Some(evt) = s_rx.recv().fuse() => {
+                match evt {
+                    SyncEvent::Status(status) => {
+                        state.sync_status = Some(status);
+                    }
+                    SyncEvent::Done(done) => {
+                        state.sync_done = Some(done);
+                    }
+                }
+                None
+            }
+            Some(results) = r_rx.recv().fuse() => {
+                state.search_results = Some(results);
+                None
+            }
             maybe_event = e_rx.recv().fuse() => {
                 maybe_event
             }
@@ -79,6 +190,13 @@ impl App {
             if let Some(event) = maybe_event {
                 stack.handle_app_event(&mut state, event);
             }
+            if let Some(request) = state.sync_request.take() {
+                sync_worker.run(state.data.clone(), request);
+            }
+            if let Some(path) = state.open_in_editor.take() {
+                self.open_in_editor(&mut terminal_poller, &mut state, &mut stack, path)
+                    .await?;
+            }
 
             self.terminal.draw(|f| stack.render(&mut state, f))?;
         }
@@ -35,14 +35,23 @@ impl AppStack {
         };
         match cur.handle_app_event(state, &event) {
             EventState::Handled => {}
-            #[cfg(debug_assertions)]
             EventState::PushStack(it) => {
                 self.push(it);
             }
-            #[cfg(debug_assertions)]
             EventState::PushBlockStack(it) => {
                 self.push_block(it);
             }
+            EventState::PopStack => {
+                if self.stack.len() > 1 {
+                    self.stack.pop();
+                }
+            }
+            EventState::ReplaceStack(it) => {
+                if self.stack.len() > 1 {
+                    self.stack.pop();
+                }
+                self.push(it);
+            }
             EventState::NotHandled => match event {
                 AppEvent::Key(KeyCode::Char('q'), KeyModifiers::CONTROL) => {
                     self.should_quit = true;
@@ -78,7 +87,7 @@ impl AppStack {
             }
         }
         if self.is_blocked {
-            statusline_help("Quit: q", screen[1], frame.buffer_mut());
+            statusline_help("Quit: q", screen[1], frame.buffer_mut(), &state.theme);
         } else if let Some(it) = self.stack.last_mut()
             && let Some(new_cursor_pos) = it.render_statusline(screen[1], frame.buffer_mut(), state)
         {
@@ -1,10 +1,186 @@
-use bookmarks_data::BookmarkFile;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
 
-use crate::tui::app::view::loader::Loader;
+use bookmarks_data::{BookmarkFile, BookmarkRecord, Diagnostic, RecordTokens};
+use bookmarks_sync::SyncStatus;
+
+use crate::{
+    config::{Collection, Keymap, Theme},
+    tui::{
+        app::view::loader::Loader,
+        search::{SearchMode, SearchQuery, SearchRequest, SearchResults},
+        sync::{SyncDone, SyncRequest},
+    },
+};
+
+/// Structural changes to `AppState.items` that views holding indices must apply.
+#[derive(Debug, Clone, Copy)]
+pub enum ItemsChange {
+    Removed(usize),
+}
 
 #[derive(Default, Debug, Clone)]
 pub struct AppState {
+    /// Data directory of the first collection, the one synced
+    pub data: PathBuf,
+    pub collections: Vec<Collection>,
     pub items: Vec<BookmarkFile>,
+    /// Search worker, its copy of `items` is kept in sync by the item methods below
+    pub searcher: Option<mpsc::Sender<SearchRequest>>,
+    /// Latest results of the search worker, not yet taken by a view
+    pub search_results: Option<SearchResults>,
+    pub search_generation: u64,
+    pub removals: u64,
     pub items_loaded: bool,
+    /// Collections done loading, successfully or not
+    pub collections_loaded: usize,
+    pub diagnostics: Vec<Diagnostic>,
+    pub changes: Vec<ItemsChange>,
+    pub sync_status: Option<SyncStatus>,
+    /// Git work for the sync worker once the current event is handled
+    pub sync_request: Option<SyncRequest>,
+    /// Latest outcome of a `sync_request`, not yet taken by a view
+    pub sync_done: Option<SyncDone>,
     pub loader: Loader,
+    /// Query for the main list to search, set by other views
+    pub search_query: Option<String>,
+    /// File to hand to `$EDITOR` once the current event is handled
+    pub open_in_editor: Option<PathBuf>,
+    pub keys: Keymap,
+    pub theme: Theme,
+}
+
+impl AppState {
+    pub fn position_of(&self, path: &Path) -> Option<usize> {
+        self.items.iter().position(|it| it.path == path)
+    }
+
+    /// Counts a loaded collection, the items are loaded once every collection is
+    pub fn collection_loaded(&mut self) {
+        self.collections_loaded += 1;
+        self.items_loaded = self.collections_loaded >= self.collections.len();
+    }
+
+    pub fn collection(&self, name: Option<&str>) -> Option<&Collection> {
+        self.collections
+            .iter()
+            .find(|it| Some(it.name.as_str()) == name)
+    }
+
+    /// Collection containing `path`
+    pub fn collection_of(&self, path: &Path) -> Option<&Collection> {
+        self.collections
+            .iter()
+            .filter(|it| path.starts_with(&it.path))
+            .max_by_key(|it| it.path.components().count())
+    }
+
+    /// Reads the file of a collection, `path` may also be relative to the first one
+    pub fn load_file(&self, path: &Path) -> io::Result<BookmarkFile> {
+        let collection = self.collection_of(path);
+        let root = collection.map_or(self.data.as_path(), |it| it.path.as_path());
+        let mut file = bookmarks_data::load_file(root, path)?;
+        file.content.collection = collection
+            .or(self.collections.first())
+            .map(|it| it.name.clone());
+        Ok(file)
+    }
+
+    /// Splits `collection:folder/sub` into the collection and the folder.
+    /// Without a known collection prefix the folder is in `default`, or the first collection.
+    pub fn parse_folder(
+        &self,
+        value: &str,
+        default: Option<&str>,
+    ) -> io::Result<(Collection, Vec<String>)> {
+        let (collection, folder) = match value
+            .split_once(':')
+            .and_then(|(name, folder)| Some((self.collection(Some(name))?, folder)))
+        {
+            Some(found) => found,
+            None => (
+                self.collection(default)
+                    .or(self.collections.first())
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No collection"))?,
+                value,
+            ),
+        };
+        let folder = folder
+            .split('/')
+            .map(str::trim)
+            .filter(|it| !it.is_empty())
+            .map(String::from)
+            .collect();
+        Ok((collection.clone(), folder))
+    }
+
+    /// `folder/sub` prefixed with the collection when there are several, as read by [`Self::parse_folder`]
+    pub fn folder_label(&self, record: &BookmarkRecord) -> String {
+        let folder = record.path.join("/");
+        match &record.collection {
+            Some(name) if self.collections.len() > 1 => format!("{name}:{folder}"),
+            _ => folder,
+        }
+    }
+
+    fn send_search(&self, request: SearchRequest) {
+        if let Some(searcher) = &self.searcher
+            && searcher.send(request).is_err()
+        {
+            tracing::warn!("Search worker stopped");
+        }
+    }
+
+    pub fn push_item(&mut self, file: BookmarkFile, tokens: Option<RecordTokens>) {
+        self.send_search(SearchRequest::Push(
+            Box::new(file.content.clone()),
+            tokens.map(Box::new),
+        ));
+        self.items.push(file);
+    }
+
+    /// Re-indexes the item after it was changed in place
+    pub fn item_changed(&mut self, index: usize) {
+        let record = Box::new(self.items[index].content.clone());
+        self.send_search(SearchRequest::Update(index, record));
+    }
+
+    /// Replaces the item with the path of `file` after it changed on disk, adds it when new
+    pub fn reload_item(&mut self, file: BookmarkFile) {
+        self.diagnostics.retain(|it| it.path != file.path);
+        match self.position_of(&file.path) {
+            Some(index) => {
+                self.items[index] = file;
+                self.item_changed(index);
+            }
+            None => self.push_item(file, None),
+        }
+    }
+
+    pub fn remove_item(&mut self, index: usize) -> BookmarkFile {
+        self.changes.push(ItemsChange::Removed(index));
+        self.send_search(SearchRequest::Remove(index));
+        self.removals += 1;
+        self.items.remove(index)
+    }
+
+    /// Starts searching `value` in the background, results arrive in `search_results`
+    /// with the returned generation, again whenever the items change.
+    pub fn search(&mut self, mode: SearchMode, value: &str) -> u64 {
+        self.search_generation += 1;
+        self.send_search(SearchRequest::Query(Some(SearchQuery {
+            generation: self.search_generation,
+            mode,
+            value: value.to_string(),
+        })));
+        self.search_generation
+    }
+
+    pub fn cancel_search(&mut self) {
+        self.search_results = None;
+        self.send_search(SearchRequest::Query(None));
+    }
 }
@@ -0,0 +1,90 @@
+use std::io;
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::tui::{
+    app::{
+        state::AppState,
+        view::{EventState, View, error::ErrorView, statusline_help},
+    },
+    event::AppEvent,
+};
+
+type ConfirmAction = Box<dyn FnOnce(&mut AppState) -> io::Result<()>>;
+
+/// Yes/no popup guarding a destructive action
+pub struct ConfirmView {
+    message: String,
+    action: Option<ConfirmAction>,
+}
+
+impl ConfirmView {
+    pub fn new(
+        message: impl Into<String>,
+        action: impl FnOnce(&mut AppState) -> io::Result<()> + 'static,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            action: Some(Box::new(action)),
+        }
+    }
+}
+
+impl View for ConfirmView {
+    fn handle_app_event(&mut self, state: &mut AppState, event: &AppEvent) -> EventState {
+        match event {
+            AppEvent::Key(KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter, _) => {
+                match self.action.take().map(|action| action(state)) {
+                    Some(Err(err)) => {
+                        EventState::ReplaceStack(Box::new(ErrorView::new(err.to_string())))
+                    }
+                    _ => EventState::PopStack,
+                }
+            }
+            AppEvent::Key(KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc, _) => {
+                EventState::PopStack
+            }
+            _ => EventState::NotHandled,
+        }
+    }
+
+    fn render_statusline(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &mut AppState,
+    ) -> Option<Position> {
+        statusline_help(
+            "Confirm: y | Cancel: n | Quit Application: c-q",
+            area,
+            buf,
+            &state.theme,
+        );
+        None
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, state: &mut AppState) -> Option<Position> {
+        let popup_area = Rect {
+            x: area.width / 3,
+            y: area.height / 4,
+            width: area.width / 3,
+            height: area.height / 4,
+        };
+        Clear.render(popup_area, buf);
+        Paragraph::new(self.message.as_str())
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::new()
+                    .title("Confirm")
+                    .title_style(Style::new().fg(state.theme.accent).bold())
+                    .borders(Borders::ALL)
+                    .border_style(Style::new().fg(state.theme.accent)),
+            )
+            .render(popup_area, buf);
+        None
+    }
+}
@@ -0,0 +1,278 @@
+use std::{collections::HashMap, io, path::Path};
+
+use bookmarks_sync::{FileConflict, GitSync, MergeStrategy, Side};
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Clear, List, ListState, Paragraph, Wrap},
+};
+
+use crate::tui::{
+    app::{
+        state::AppState,
+        view::{EventState, View, statusline_help},
+    },
+    event::AppEvent,
+    sync::{SyncDone, SyncRequest},
+};
+
+/// Resolves sync conflicts left after the field merge, one field at a time
+pub struct ConflictView {
+    conflicts: Vec<FileConflict>,
+    /// (conflict index, field index), the field is `None` for files that can not be merged
+    rows: Vec<(usize, Option<usize>)>,
+    rows_state: ListState,
+    choices: HashMap<(usize, usize), Side>,
+    message: Option<Line<'static>>,
+    /// A request runs in the sync worker, the next one waits for it
+    pending: bool,
+}
+
+impl ConflictView {
+    pub fn open(data: &Path) -> io::Result<Self> {
+        let git = GitSync::open(data)?;
+        let conflicts = git.conflict_details(MergeStrategy::Markers)?;
+        let mut view = Self {
+            conflicts: vec![],
+            rows: vec![],
+            rows_state: ListState::default(),
+            choices: HashMap::new(),
+            message: None,
+            pending: false,
+        };
+        view.set_conflicts(conflicts);
+        Ok(view)
+    }
+
+    fn set_conflicts(&mut self, conflicts: Vec<FileConflict>) {
+        self.conflicts = conflicts;
+        self.choices.clear();
+        self.rows = self
+            .conflicts
+            .iter()
+            .enumerate()
+            .flat_map(|(i, conflict)| match conflict.merged {
+                Some(_) => (0..conflict.fields.len()).map(|f| (i, Some(f))).collect(),
+                None => vec![(i, None)],
+            })
+            .collect();
+        self.rows_state.select((!self.rows.is_empty()).then_some(0));
+    }
+
+    fn selected(&self) -> Option<(usize, Option<usize>)> {
+        self.rows_state
+            .selected()
+            .and_then(|i| self.rows.get(i))
+            .copied()
+    }
+
+    fn choose(&mut self, side: Side) {
+        if let Some((i, Some(f))) = self.selected() {
+            self.choices.insert((i, f), side);
+            self.rows_state.select_next();
+        }
+    }
+
+    /// Asks to write the file of the selected row once every field has a choice
+    fn write(&mut self, state: &mut AppState) {
+        let Some((i, _)) = self.selected() else {
+            return;
+        };
+        let conflict = &self.conflicts[i];
+        let Some(mut record) = conflict.merged.clone() else {
+            self.message = Some(
+                "Edit this file manually, then continue"
+                    .fg(state.theme.warning)
+                    .into(),
+            );
+            return;
+        };
+        for (f, field) in conflict.fields.iter().enumerate() {
+            let Some(side) = self.choices.get(&(i, f)) else {
+                self.message = Some(
+                    format!("Pick a side for {}", field.field)
+                        .fg(state.theme.warning)
+                        .into(),
+                );
+                return;
+            };
+            field.apply(&mut record, *side);
+        }
+        self.request(
+            state,
+            SyncRequest::Resolve(conflict.path.clone(), Box::new(record)),
+        );
+    }
+
+    fn request(&mut self, state: &mut AppState, request: SyncRequest) {
+        state.sync_done = None;
+        state.sync_request = Some(request);
+        self.pending = true;
+        self.message = Some("Working…".dim().into());
+    }
+
+    /// Takes the outcome of the pending request
+    fn apply_done(&mut self, state: &mut AppState) -> io::Result<()> {
+        if !self.pending {
+            return Ok(());
+        }
+        let Some(done) = state.sync_done.take() else {
+            return Ok(());
+        };
+        self.pending = false;
+        match done {
+            SyncDone::Resolved(path, result) => {
+                result.map_err(io::Error::other)?;
+                let file = state.data.join(&path);
+                if let Some(index) = state.position_of(&file) {
+                    state.items[index] = state.load_file(&file)?;
+                    state.item_changed(index);
+                }
+                self.message = Some(
+                    format!("Resolved {}", path.to_string_lossy())
+                        .fg(state.theme.success)
+                        .into(),
+                );
+                let mut conflicts = std::mem::take(&mut self.conflicts);
+                conflicts.retain(|it| it.path != path);
+                self.set_conflicts(conflicts);
+            }
+            SyncDone::Resumed(result) => {
+                let report = result.map_err(io::Error::other)?;
+                self.message = Some(if report.conflicts.is_empty() {
+                    "Sync finished".fg(state.theme.success).into()
+                } else {
+                    "More conflicts in the next commit"
+                        .fg(state.theme.warning)
+                        .into()
+                });
+                self.set_conflicts(report.conflicts);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl View for ConflictView {
+    fn handle_app_event(&mut self, state: &mut AppState, event: &AppEvent) -> EventState {
+        match event {
+            AppEvent::Key(KeyCode::Char('j') | KeyCode::Down, _) => {
+                self.rows_state.select_next();
+            }
+            AppEvent::Key(KeyCode::Char('k') | KeyCode::Up, _) => {
+                self.rows_state.select_previous();
+            }
+            AppEvent::Key(KeyCode::Char('l'), _) => self.choose(Side::Local),
+            AppEvent::Key(KeyCode::Char('r'), _) => self.choose(Side::Remote),
+            AppEvent::Key(KeyCode::Char('w' | 'c'), _) if self.pending => {}
+            AppEvent::Key(KeyCode::Char('w'), _) => self.write(state),
+            AppEvent::Key(KeyCode::Char('c'), _) => self.request(state, SyncRequest::Resume),
+            AppEvent::Key(KeyCode::Esc, _) => return EventState::PopStack,
+            _ => return EventState::NotHandled,
+        }
+        EventState::Handled
+    }
+
+    fn render_statusline(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &mut AppState,
+    ) -> Option<Position> {
+        statusline_help(
+            "Close: q | Next: j | Prev: k | Keep local: l | Keep remote: r | Write file: w | Continue sync: c",
+            area,
+            buf,
+            &state.theme,
+        );
+        None
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, state: &mut AppState) -> Option<Position> {
+        if let Err(err) = self.apply_done(state) {
+            self.message = Some(err.to_string().fg(state.theme.error).into());
+        }
+        let popup_area = area.inner(Margin {
+            horizontal: area.width / 8,
+            vertical: area.height / 8,
+        });
+        Clear.render(popup_area, buf);
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(Line::from("Sync conflicts".bold()).left_aligned());
+        let inner = block.inner(popup_area);
+        block.render(popup_area, buf);
+
+        let [body, message_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+        let [rows_area, details_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Fill(1)]).areas(body);
+
+        if let Some(message) = self.message.clone() {
+            message.render(message_area, buf);
+        }
+        if self.rows.is_empty() {
+            Paragraph::new("No conflicts left, continue the sync with c".dim())
+                .render(rows_area, buf);
+            return None;
+        }
+
+        let rows = self
+            .rows
+            .iter()
+            .map(|(i, f)| {
+                let conflict = &self.conflicts[*i];
+                let mut line = Line::from(conflict.path.to_string_lossy().to_string());
+                match f {
+                    Some(f) => {
+                        line.push_span(" ");
+                        line.push_span(conflict.fields[*f].field.to_string().fg(state.theme.info));
+                        match self.choices.get(&(*i, *f)) {
+                            Some(Side::Local) => line.push_span(" [local]".fg(state.theme.success)),
+                            Some(Side::Remote) => {
+                                line.push_span(" [remote]".fg(state.theme.success))
+                            }
+                            None => line.push_span(" [?]".fg(state.theme.error)),
+                        }
+                    }
+                    None => line.push_span(" [manual]".fg(state.theme.error)),
+                }
+                line
+            })
+            .collect::<List>()
+            .block(Block::bordered().border_type(BorderType::Rounded))
+            .highlight_style(Style::new().reversed());
+        StatefulWidget::render(rows, rows_area, buf, &mut self.rows_state);
+
+        let mut text = Text::default();
+        match self.selected() {
+            Some((i, Some(f))) => {
+                let field = &self.conflicts[i].fields[f];
+                for (label, value) in [
+                    ("Base", field.base.as_deref()),
+                    ("Local", field.local.as_deref()),
+                    ("Remote", field.remote.as_deref()),
+                ] {
+                    text.push_line(Line::from(label.bold()));
+                    for line in value.unwrap_or("<empty>").lines() {
+                        text.push_line(line.to_string());
+                    }
+                    text.push_line("");
+                }
+            }
+            Some((i, None)) => {
+                text.push_line(format!(
+                    "{} was deleted on one side or can not be parsed.",
+                    self.conflicts[i].path.to_string_lossy()
+                ));
+                text.push_line("Fix it in an editor, then continue the sync.");
+            }
+            None => {}
+        }
+        Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().border_type(BorderType::Rounded))
+            .render(details_area, buf);
+        None
+    }
+}
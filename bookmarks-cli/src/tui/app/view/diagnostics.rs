@@ -0,0 +1,116 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Clear, List, ListState, Paragraph},
+};
+
+use crate::tui::{
+    app::{
+        state::AppState,
+        view::{EventState, View, statusline_help},
+    },
+    event::AppEvent,
+};
+
+/// Files that failed to load
+#[derive(Debug, Default)]
+pub struct DiagnosticsView {
+    list_state: ListState,
+}
+
+impl View for DiagnosticsView {
+    fn handle_app_event(&mut self, _state: &mut AppState, event: &AppEvent) -> EventState {
+        match event {
+            AppEvent::Key(KeyCode::Char('j') | KeyCode::Down, _) => {
+                self.list_state.select_next();
+                EventState::Handled
+            }
+            AppEvent::Key(KeyCode::Char('k') | KeyCode::Up, _) => {
+                self.list_state.select_previous();
+                EventState::Handled
+            }
+            AppEvent::Key(KeyCode::Esc, _) => EventState::PopStack,
+            _ => EventState::NotHandled,
+        }
+    }
+
+    fn render_statusline(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &mut AppState,
+    ) -> Option<Position> {
+        statusline_help(
+            "Close: q | Next: j | Prev: k | Quit Application: c-q",
+            area,
+            buf,
+            &state.theme,
+        );
+        None
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, state: &mut AppState) -> Option<Position> {
+        let popup_area = area.inner(Margin {
+            horizontal: area.width / 8,
+            vertical: area.height / 6,
+        });
+        Clear.render(popup_area, buf);
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::new().fg(state.theme.error))
+            .title(
+                Line::from(format!("Broken files ({})", state.diagnostics.len()).bold())
+                    .left_aligned(),
+            );
+
+        if state.diagnostics.is_empty() {
+            Paragraph::new("All files loaded".dim())
+                .block(block)
+                .render(popup_area, buf);
+            return None;
+        }
+        if self.list_state.selected().is_none() {
+            self.list_state.select_first();
+        }
+
+        let list = state
+            .diagnostics
+            .iter()
+            .map(|it| {
+                let mut location = match state.collection_of(&it.path) {
+                    Some(collection) if state.collections.len() > 1 => format!(
+                        "{}:{}",
+                        collection.name,
+                        it.path
+                            .strip_prefix(&collection.path)
+                            .unwrap_or(&it.path)
+                            .to_string_lossy()
+                    ),
+                    _ => it
+                        .path
+                        .strip_prefix(&state.data)
+                        .unwrap_or(&it.path)
+                        .to_string_lossy()
+                        .to_string(),
+                };
+                if let Some((line, column)) = it.location {
+                    location.push_str(&format!(":{line}:{column}"));
+                }
+                let mut text = Text::default();
+                text.push_line(Line::from(vec![
+                    location.bold(),
+                    " ".into(),
+                    format!("{:?}", it.kind)
+                        .to_lowercase()
+                        .fg(state.theme.error),
+                ]));
+                text.push_line(Line::from(it.message.clone().dim()));
+                text
+            })
+            .collect::<List>()
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        StatefulWidget::render(list, popup_area, buf, &mut self.list_state);
+        None
+    }
+}
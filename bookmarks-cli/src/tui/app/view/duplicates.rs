@@ -0,0 +1,189 @@
+use std::path::PathBuf;
+
+use bookmarks_data::{BookmarkFile, DuplicateGroup};
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Clear, List, ListState, Paragraph},
+};
+
+use crate::tui::{
+    app::{
+        state::AppState,
+        view::{EventState, View, confirm::ConfirmView, statusline_help},
+    },
+    event::AppEvent,
+};
+
+/// Bookmarks sharing an url, merges a group into the chosen file
+#[derive(Debug, Default)]
+pub struct DuplicatesView {
+    list_state: ListState,
+    groups: Vec<DuplicateGroup>,
+    /// `(items, removals)` of the state the groups were found in
+    found_in: Option<(usize, u64)>,
+}
+
+/// A line of the list: the group header or one of its items
+enum Row {
+    Group(usize),
+    Item(usize, usize),
+}
+
+impl DuplicatesView {
+    /// Finds the groups again once the items changed
+    fn refresh(&mut self, state: &AppState) {
+        let key = (state.items.len(), state.removals);
+        if !state.items_loaded || self.found_in == Some(key) {
+            return;
+        }
+        self.groups = bookmarks_data::find_duplicates(state.items.iter().map(|it| &it.content));
+        self.found_in = Some(key);
+        let last = self.rows().len().checked_sub(1);
+        let selected = self.list_state.selected().unwrap_or_default();
+        self.list_state.select(last.map(|last| selected.min(last)));
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        self.groups
+            .iter()
+            .enumerate()
+            .flat_map(|(g, group)| {
+                std::iter::once(Row::Group(g))
+                    .chain(group.records.iter().map(move |i| Row::Item(g, *i)))
+            })
+            .collect()
+    }
+
+    /// Asks to merge the group of the selected row into its item, the first one for the header
+    fn merge(&self, state: &AppState) -> Option<EventState> {
+        let rows = self.rows();
+        let (group, kept) = match rows.get(self.list_state.selected()?)? {
+            Row::Group(g) => (&self.groups[*g], self.groups[*g].records[0]),
+            Row::Item(g, i) => (&self.groups[*g], *i),
+        };
+        let kept_file = state.items.get(kept)?;
+        let kept = kept_file.path.clone();
+        let others: Vec<PathBuf> = group
+            .records
+            .iter()
+            .filter_map(|i| state.items.get(*i))
+            .map(|it| it.path.clone())
+            .filter(|it| *it != kept)
+            .collect();
+        Some(EventState::PushStack(Box::new(ConfirmView::new(
+            format!(
+                "Merge {} duplicate(s) into {} and delete them?",
+                others.len(),
+                kept_file.relative_path.to_string_lossy()
+            ),
+            move |state| {
+                let Some(index) = state.position_of(&kept) else {
+                    return Ok(());
+                };
+                let others: Vec<BookmarkFile> = others
+                    .iter()
+                    .filter_map(|it| state.position_of(it))
+                    .map(|i| state.items[i].clone())
+                    .collect();
+                let merged = bookmarks_data::merge_duplicates(&state.items[index], &others)?;
+                state.reload_item(merged);
+                for other in &others {
+                    if let Some(index) = state.position_of(&other.path) {
+                        state.remove_item(index);
+                    }
+                }
+                Ok(())
+            },
+        ))))
+    }
+}
+
+impl View for DuplicatesView {
+    fn handle_app_event(&mut self, state: &mut AppState, event: &AppEvent) -> EventState {
+        self.refresh(state);
+        match event {
+            AppEvent::Key(KeyCode::Char('j') | KeyCode::Down, _) => {
+                self.list_state.select_next();
+                EventState::Handled
+            }
+            AppEvent::Key(KeyCode::Char('k') | KeyCode::Up, _) => {
+                self.list_state.select_previous();
+                EventState::Handled
+            }
+            AppEvent::Key(KeyCode::Enter, _) => self.merge(state).unwrap_or(EventState::Handled),
+            AppEvent::Key(KeyCode::Esc | KeyCode::Char('q'), _) => EventState::PopStack,
+            _ => EventState::NotHandled,
+        }
+    }
+
+    fn render_statusline(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &mut AppState,
+    ) -> Option<Position> {
+        statusline_help(
+            "Close: esc | Next: j | Prev: k | Merge into selected: return | Quit Application: c-q",
+            area,
+            buf,
+            &state.theme,
+        );
+        None
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, state: &mut AppState) -> Option<Position> {
+        self.refresh(state);
+        let popup_area = area.inner(Margin {
+            horizontal: area.width / 8,
+            vertical: area.height / 8,
+        });
+        Clear.render(popup_area, buf);
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::new().fg(state.theme.accent))
+            .title(Line::from(format!("Duplicates ({})", self.groups.len()).bold()).left_aligned());
+
+        let empty = match (state.items_loaded, self.groups.is_empty()) {
+            (false, _) => Some("Loading…"),
+            (true, true) => Some("No duplicates"),
+            (true, false) => None,
+        };
+        if let Some(message) = empty {
+            Paragraph::new(message.dim())
+                .block(block)
+                .render(popup_area, buf);
+            return None;
+        }
+
+        let list = self
+            .rows()
+            .into_iter()
+            .map(|row| match row {
+                Row::Group(g) => {
+                    let group = &self.groups[g];
+                    Line::from(vec![
+                        group.url.clone().fg(state.theme.accent).bold(),
+                        format!(" ({})", group.records.len()).dim(),
+                    ])
+                }
+                Row::Item(_, i) => {
+                    let Some(item) = state.items.get(i) else {
+                        return Line::default();
+                    };
+                    let record = &item.content;
+                    let tags = record.tags.as_deref().unwrap_or_default().join(", ");
+                    Line::from(vec![
+                        format!("  {} : ", state.folder_label(record)).into(),
+                        record.title.clone().unwrap_or_default().into(),
+                        format!(" {tags}").dim(),
+                    ])
+                }
+            })
+            .collect::<List>()
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        StatefulWidget::render(list, popup_area, buf, &mut self.list_state);
+        None
+    }
+}
@@ -1,53 +1,346 @@
+use std::path::PathBuf;
+
+use bookmarks_data::{BookmarkFile, BookmarkRecord};
+use crossterm::event::{KeyCode, KeyModifiers};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    widgets::{Block, BorderType, Clear, Paragraph},
 };
 
 use crate::tui::{
     app::{
         state::AppState,
-        view::{EventState, View, statusline_help},
+        view::{EventState, View, input::TextInput, statusline_help},
     },
     event::AppEvent,
 };
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    #[default]
+    Title,
+    Url,
+    Tags,
+    Description,
+    Folder,
+}
+
+impl Field {
+    fn next(self, with_folder: bool) -> Self {
+        match self {
+            Field::Title => Field::Url,
+            Field::Url => Field::Tags,
+            Field::Tags => Field::Description,
+            Field::Description if with_folder => Field::Folder,
+            Field::Description | Field::Folder => Field::Title,
+        }
+    }
+
+    fn prev(self, with_folder: bool) -> Self {
+        match self {
+            Field::Title if with_folder => Field::Folder,
+            Field::Title => Field::Description,
+            Field::Url => Field::Title,
+            Field::Tags => Field::Url,
+            Field::Description => Field::Tags,
+            Field::Folder => Field::Description,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct EditView(pub Option<usize>);
+pub struct EditView {
+    /// File of the edited bookmark, `None` when creating a new one
+    path: Option<PathBuf>,
+    focused: Field,
+    title: TextInput,
+    url: TextInput,
+    tags: TextInput,
+    description: TextInput,
+    folder: TextInput,
+    error: Option<String>,
+}
+
+impl EditView {
+    pub fn new(file: &BookmarkFile) -> Self {
+        let content = &file.content;
+        Self {
+            path: Some(file.path.clone()),
+            focused: Field::default(),
+            title: TextInput::new(content.title.clone().unwrap_or_default()),
+            url: TextInput::new(content.url.clone()),
+            tags: TextInput::new(content.tags.as_deref().unwrap_or_default().join(", ")),
+            description: TextInput::new(content.description.clone().unwrap_or_default())
+                .multiline(),
+            folder: TextInput::default(),
+            error: None,
+        }
+    }
+
+    /// Form for a new bookmark placed in `folder`, see [`AppState::parse_folder`]
+    pub fn create(folder: String) -> Self {
+        Self {
+            path: None,
+            focused: Field::Url,
+            title: TextInput::default(),
+            url: TextInput::default(),
+            tags: TextInput::default(),
+            description: TextInput::default().multiline(),
+            folder: TextInput::new(folder),
+            error: None,
+        }
+    }
+
+    fn with_folder(&self) -> bool {
+        self.path.is_none()
+    }
+
+    fn focused_input(&mut self) -> &mut TextInput {
+        match self.focused {
+            Field::Title => &mut self.title,
+            Field::Url => &mut self.url,
+            Field::Tags => &mut self.tags,
+            Field::Description => &mut self.description,
+            Field::Folder => &mut self.folder,
+        }
+    }
+
+    fn apply(&self, content: &mut BookmarkRecord) {
+        let non_empty = |value: &str| {
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        content.title = non_empty(self.title.value());
+        content.url = self.url.value().trim().to_string();
+        let mut tags: Vec<String> = vec![];
+        for tag in self.tags.value().split(',').filter_map(non_empty) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        content.tags = (!tags.is_empty()).then_some(tags);
+        content.description = non_empty(self.description.value());
+    }
+
+    fn save(&mut self, state: &mut AppState) -> EventState {
+        let result = match &self.path {
+            Some(path) => {
+                // Looked up again, the item may have moved since the form opened
+                let Some(item_index) = state.position_of(path) else {
+                    self.error = Some(format!("{} no longer exists", path.to_string_lossy()));
+                    return EventState::Handled;
+                };
+                let mut updated = state.items[item_index].clone();
+                self.apply(&mut updated.content);
+                updated
+                    .content
+                    .validate()
+                    .and_then(|_| bookmarks_data::save_to_fs(&updated))
+                    .map(|_| {
+                        state.items[item_index] = updated;
+                        state.item_changed(item_index);
+                    })
+            }
+            None => {
+                let mut record = BookmarkRecord::default();
+                self.apply(&mut record);
+                state
+                    .parse_folder(self.folder.value(), None)
+                    .and_then(|(collection, folder)| {
+                        record.path = folder;
+                        record.collection = Some(collection.name);
+                        bookmarks_data::create_in_fs(&collection.path, record)
+                    })
+                    .map(|file| state.push_item(file, None))
+            }
+        };
+        match result {
+            Ok(()) => EventState::PopStack,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                EventState::Handled
+            }
+        }
+    }
+}
 
 impl View for EditView {
-    fn handle_app_event(&mut self, _state: &mut AppState, _event: &AppEvent) -> EventState {
-        EventState::NotHandled
+    fn handle_app_event(&mut self, state: &mut AppState, event: &AppEvent) -> EventState {
+        let AppEvent::Key(code, modifiers) = event else {
+            return EventState::NotHandled;
+        };
+        match (code, *modifiers) {
+            (KeyCode::Esc, _) => EventState::PopStack,
+            (KeyCode::Char('s'), KeyModifiers::CONTROL) => self.save(state),
+            (KeyCode::Tab, _) => {
+                self.focused = self.focused.next(self.with_folder());
+                EventState::Handled
+            }
+            (KeyCode::BackTab, _) => {
+                self.focused = self.focused.prev(self.with_folder());
+                EventState::Handled
+            }
+            _ if self.focused_input().handle_key(*code, *modifiers) => {
+                self.error = None;
+                EventState::Handled
+            }
+            (KeyCode::Enter | KeyCode::Down, _) => {
+                self.focused = self.focused.next(self.with_folder());
+                EventState::Handled
+            }
+            (KeyCode::Up, _) => {
+                self.focused = self.focused.prev(self.with_folder());
+                EventState::Handled
+            }
+            _ => EventState::NotHandled,
+        }
     }
 
     fn render_statusline(
         &mut self,
         area: Rect,
         buf: &mut Buffer,
-        _state: &mut AppState,
+        state: &mut AppState,
     ) -> Option<Position> {
-        statusline_help("Close: q | Quit Application: c-q", area, buf);
+        statusline_help(
+            "Save: c-s | Next field: tab | Prev field: s-tab | Cancel: esc | Quit Application: c-q",
+            area,
+            buf,
+            &state.theme,
+        );
         None
     }
 
-    fn render(&mut self, area: Rect, buf: &mut Buffer, _state: &mut AppState) -> Option<Position> {
-        let popup_area = Rect {
-            x: area.width / 3,
-            y: area.height / 4,
-            width: area.width / 3,
-            height: area.height / 4,
-        };
+    fn render(&mut self, area: Rect, buf: &mut Buffer, state: &mut AppState) -> Option<Position> {
+        let popup_area = area.inner(Margin {
+            horizontal: area.width / 6,
+            vertical: area.height / 8,
+        });
         Clear.render(popup_area, buf);
-        Paragraph::new(format!("{:?}", self.0))
-            .wrap(Wrap { trim: true })
-            .style(Style::new().red())
-            .block(
-                Block::new()
-                    .title("Error")
-                    .title_style(Style::new().red().bold())
-                    .borders(Borders::ALL)
-                    .border_style(Style::new().red()),
-            )
-            .render(popup_area, buf);
-        None
+
+        let title = match self.path {
+            Some(_) => "Edit bookmark",
+            None => "New bookmark",
+        };
+        let mut block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(Line::from(title.bold()).left_aligned());
+        if let Some(file) = self
+            .path
+            .as_ref()
+            .and_then(|it| state.position_of(it))
+            .map(|i| &state.items[i])
+            && let Some(relative_path) = file.relative_path.to_str()
+        {
+            block =
+                block.title(Line::from(relative_path.dim().fg(state.theme.folder)).right_aligned());
+        }
+        let inner = block.inner(popup_area);
+        block.render(popup_area, buf);
+
+        let folder_height = if self.with_folder() { 3 } else { 0 };
+        let layout = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+            Constraint::Length(folder_height),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+        let focused = self.focused;
+        let field_block = |field: Field, title: &'static str| {
+            let block = Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title(title);
+            if field == focused {
+                block.border_style(Style::new().fg(state.theme.accent))
+            } else {
+                block
+            }
+        };
+
+        let cursor = [
+            self.title.render(
+                field_block(Field::Title, "Title"),
+                focused == Field::Title,
+                layout[0],
+                buf,
+            ),
+            self.url.render(
+                field_block(Field::Url, "Url"),
+                focused == Field::Url,
+                layout[1],
+                buf,
+            ),
+            self.tags.render(
+                field_block(Field::Tags, "Tags (comma separated)"),
+                focused == Field::Tags,
+                layout[2],
+                buf,
+            ),
+            self.description.render(
+                field_block(Field::Description, "Description"),
+                focused == Field::Description,
+                layout[3],
+                buf,
+            ),
+            self.folder.render(
+                field_block(
+                    Field::Folder,
+                    match state.collections.len() {
+                        1 => "Folder (slash separated)",
+                        _ => "Folder (collection:folder/sub)",
+                    },
+                ),
+                focused == Field::Folder,
+                layout[4],
+                buf,
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .next();
+
+        if let Some(error) = self.error.as_deref() {
+            Paragraph::new(error.fg(state.theme.error)).render(layout[5], buf);
+        }
+
+        cursor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create(root: &std::path::Path, title: &str) -> BookmarkFile {
+        let record = BookmarkRecord {
+            title: Some(title.to_string()),
+            url: format!("https://example.com/{title}"),
+            ..Default::default()
+        };
+        bookmarks_data::create_in_fs(root, record).unwrap()
+    }
+
+    #[test]
+    fn save_follows_the_file_after_removals() {
+        let root = tempfile::tempdir().unwrap();
+        let mut state = AppState::default();
+        state.push_item(create(root.path(), "a"), None);
+        state.push_item(create(root.path(), "b"), None);
+        let mut view = EditView::new(&state.items[1]);
+        view.title = TextInput::new("B edited".to_string());
+
+        state.remove_item(0);
+        assert!(matches!(view.save(&mut state), EventState::PopStack));
+        assert_eq!(state.items[0].content.title.as_deref(), Some("B edited"));
+        let saved = bookmarks_data::load_file(root.path(), &state.items[0].relative_path).unwrap();
+        assert_eq!(saved.content.title.as_deref(), Some("B edited"));
+
+        state.remove_item(0);
+        assert!(matches!(view.save(&mut state), EventState::Handled));
+        assert!(view.error.is_some());
     }
 }
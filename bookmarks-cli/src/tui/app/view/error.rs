@@ -1,3 +1,6 @@
+use std::path::PathBuf;
+
+use crossterm::event::KeyCode;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
@@ -12,24 +15,57 @@ use crate::tui::{
 };
 
 #[derive(Debug, Clone)]
-pub struct ErrorView(pub String);
+pub struct ErrorView {
+    message: String,
+    /// File the error is about, can be opened in `$EDITOR` again to fix it
+    reopen: Option<PathBuf>,
+}
+
+impl ErrorView {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            reopen: None,
+        }
+    }
+
+    pub fn reopen(mut self, path: PathBuf) -> Self {
+        self.reopen = Some(path);
+        self
+    }
+}
 
 impl View for ErrorView {
-    fn handle_app_event(&mut self, _state: &mut AppState, _event: &AppEvent) -> EventState {
-        EventState::NotHandled
+    fn handle_app_event(&mut self, state: &mut AppState, event: &AppEvent) -> EventState {
+        match event {
+            AppEvent::Key(KeyCode::Char('E'), _) if let Some(path) = self.reopen.take() => {
+                state.open_in_editor = Some(path);
+                EventState::PopStack
+            }
+            _ => EventState::NotHandled,
+        }
     }
 
     fn render_statusline(
         &mut self,
         area: Rect,
         buf: &mut Buffer,
-        _state: &mut AppState,
+        state: &mut AppState,
     ) -> Option<Position> {
-        statusline_help("Close: q | Quit Application: c-q", area, buf);
+        if self.reopen.is_some() {
+            statusline_help(
+                "Close: q | Open in $EDITOR: E | Quit Application: c-q",
+                area,
+                buf,
+                &state.theme,
+            );
+        } else {
+            statusline_help("Close: q | Quit Application: c-q", area, buf, &state.theme);
+        }
         None
     }
 
-    fn render(&mut self, area: Rect, buf: &mut Buffer, _state: &mut AppState) -> Option<Position> {
+    fn render(&mut self, area: Rect, buf: &mut Buffer, state: &mut AppState) -> Option<Position> {
         let popup_area = Rect {
             x: area.width / 3,
             y: area.height / 4,
@@ -37,15 +73,15 @@ impl View for ErrorView {
             height: area.height / 4,
         };
         Clear.render(popup_area, buf);
-        Paragraph::new(self.0.as_str())
+        Paragraph::new(self.message.as_str())
             .wrap(Wrap { trim: true })
-            .style(Style::new().red())
+            .style(Style::new().fg(state.theme.error))
             .block(
                 Block::new()
                     .title("Error")
-                    .title_style(Style::new().red().bold())
+                    .title_style(Style::new().fg(state.theme.error).bold())
                     .borders(Borders::ALL)
-                    .border_style(Style::new().red()),
+                    .border_style(Style::new().fg(state.theme.error)),
             )
             .render(popup_area, buf);
         None
@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, HashSet};
+
+use bookmarks_data::BookmarkRecord;
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, List, ListState},
+};
+
+use crate::tui::app::state::AppState;
+
+/// A folder of the loaded items, `collection` is only set when there are several
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Folder {
+    pub collection: Option<String>,
+    pub path: Vec<String>,
+}
+
+/// Tree position of a record: its collection when there are several, and its folder
+fn location<'a>(state: &AppState, record: &'a BookmarkRecord) -> (Option<&'a str>, &'a [String]) {
+    let collection = match state.collections.len() > 1 {
+        true => record.collection.as_deref(),
+        false => None,
+    };
+    (collection, &record.path)
+}
+
+impl Folder {
+    fn new(collection: Option<&str>, path: &[String]) -> Self {
+        Self {
+            collection: collection.map(String::from),
+            path: path.to_vec(),
+        }
+    }
+
+    /// Whether the record is in this folder or below
+    pub fn contains(&self, state: &AppState, record: &BookmarkRecord) -> bool {
+        let (collection, path) = location(state, record);
+        collection == self.collection.as_deref() && path.starts_with(&self.path)
+    }
+
+    /// `folder/sub`, prefixed with the collection when there are several
+    pub fn label(&self) -> String {
+        let path = self.path.join("/");
+        match &self.collection {
+            Some(collection) => format!("{collection}:{path}"),
+            None => path,
+        }
+    }
+}
+
+struct Row {
+    /// `None` for the row of every item
+    folder: Option<Folder>,
+    depth: usize,
+    count: usize,
+    has_children: bool,
+}
+
+/// What a key press in the tree asks the main view for
+pub enum TreeEvent {
+    Handled,
+    /// Scope the list to the folder, `None` for every item
+    Choose(Option<Folder>),
+    NotHandled,
+}
+
+/// Collapsible folders of the loaded items with their item counts
+#[derive(Debug, Default)]
+pub struct FolderTree {
+    list_state: ListState,
+    expanded: HashSet<Folder>,
+}
+
+impl FolderTree {
+    /// Visible rows, every item first, then the folders depth first
+    fn rows(&self, state: &AppState) -> Vec<Row> {
+        // Single collections have no node of their own, the first row stands for it
+        let first_depth = match state.collections.len() > 1 {
+            true => 0,
+            false => 1,
+        };
+        let mut counts: BTreeMap<(Option<&str>, &[String]), usize> = BTreeMap::new();
+        for item in &state.items {
+            let (collection, path) = location(state, &item.content);
+            for depth in first_depth..=path.len() {
+                *counts.entry((collection, &path[..depth])).or_default() += 1;
+            }
+        }
+
+        let mut rows = vec![Row {
+            folder: None,
+            depth: 0,
+            count: state.items.len(),
+            has_children: !counts.is_empty(),
+        }];
+        let mut entries = counts.iter().peekable();
+        while let Some(((collection, path), count)) = entries.next() {
+            let visible = (first_depth..path.len()).all(|depth| {
+                self.expanded
+                    .contains(&Folder::new(*collection, &path[..depth]))
+            });
+            if !visible {
+                continue;
+            }
+            let has_children = entries.peek().is_some_and(|((next_collection, next), _)| {
+                next_collection == collection && next.len() > path.len() && next.starts_with(path)
+            });
+            rows.push(Row {
+                folder: Some(Folder::new(*collection, path)),
+                depth: path.len() + 1 - first_depth,
+                count: *count,
+                has_children,
+            });
+        }
+        rows
+    }
+
+    pub fn handle_key(&mut self, state: &AppState, code: KeyCode) -> TreeEvent {
+        let rows = self.rows(state);
+        let selected = self.list_state.selected().and_then(|i| rows.get(i));
+        match code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char('g') => self.list_state.select_first(),
+            KeyCode::Char('G') => self.list_state.select_last(),
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Char(' ')
+                if let Some(Row {
+                    folder: Some(folder),
+                    has_children: true,
+                    ..
+                }) = selected =>
+            {
+                let expand = code != KeyCode::Char(' ') || !self.expanded.contains(folder);
+                if expand {
+                    self.expanded.insert(folder.clone());
+                } else {
+                    self.expanded.remove(folder);
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Left
+                if let Some(Row {
+                    folder: Some(folder),
+                    depth,
+                    ..
+                }) = selected =>
+            {
+                if !self.expanded.remove(folder) {
+                    // Collapsed already, go to the parent
+                    let parent = self
+                        .list_state
+                        .selected()
+                        .and_then(|i| rows[..i].iter().rposition(|it| it.depth + 1 == *depth));
+                    self.list_state.select(parent.or(Some(0)));
+                }
+            }
+            KeyCode::Enter if let Some(row) = selected => {
+                return TreeEvent::Choose(row.folder.clone());
+            }
+            _ => return TreeEvent::NotHandled,
+        }
+        TreeEvent::Handled
+    }
+
+    pub fn render(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &AppState,
+        focused: bool,
+        scope: Option<&Folder>,
+    ) {
+        let rows = self.rows(state);
+        if self.list_state.selected().is_none_or(|it| it >= rows.len()) {
+            let scoped = rows.iter().position(|it| it.folder.as_ref() == scope);
+            self.list_state.select(scoped.or(Some(0)));
+        }
+
+        let mut block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(Line::from("Folders"));
+        if focused {
+            block = block.border_style(Style::new().fg(state.theme.accent));
+        }
+        let list = rows
+            .iter()
+            .map(|row| {
+                let marker = match (&row.folder, row.has_children) {
+                    (Some(folder), true) if self.expanded.contains(folder) => "▾ ",
+                    (Some(_), true) => "▸ ",
+                    _ => "  ",
+                };
+                let name = match &row.folder {
+                    None => "All".to_string(),
+                    Some(folder) => match folder.path.last() {
+                        Some(name) => name.clone(),
+                        None => folder.collection.clone().unwrap_or_default(),
+                    },
+                };
+                let mut name = Span::raw(name);
+                if row.folder.as_ref() == scope {
+                    name = name.fg(state.theme.accent).bold();
+                }
+                Line::from(vec![
+                    Span::raw(format!("{}{marker}", "  ".repeat(row.depth))),
+                    name,
+                    format!(" {}", row.count).dim(),
+                ])
+            })
+            .collect::<List>()
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+}
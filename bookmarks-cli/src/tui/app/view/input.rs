@@ -0,0 +1,173 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Paragraph},
+};
+
+/// Editable text buffer with a char based cursor.
+#[derive(Debug, Default, Clone)]
+pub struct TextInput {
+    value: String,
+    cursor_pos: usize,
+    multiline: bool,
+    scroll: (u16, u16),
+}
+
+impl TextInput {
+    pub fn new(value: impl Into<String>) -> Self {
+        let value = value.into();
+        let cursor_pos = value.chars().count();
+        Self {
+            value,
+            cursor_pos,
+            ..Default::default()
+        }
+    }
+
+    pub fn multiline(mut self) -> Self {
+        self.multiline = true;
+        self
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    fn byte_index(&self, char_pos: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(char_pos)
+            .map(|(i, _)| i)
+            .unwrap_or(self.value.len())
+    }
+
+    /// (first char index, length in chars) for every line
+    fn lines(&self) -> Vec<(usize, usize)> {
+        let mut start = 0;
+        self.value
+            .split('\n')
+            .map(|line| {
+                let len = line.chars().count();
+                let it = (start, len);
+                start += len + 1;
+                it
+            })
+            .collect()
+    }
+
+    /// (line, column) of the cursor
+    fn cursor_line_col(&self) -> (usize, usize) {
+        let lines = self.lines();
+        lines
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, (start, _))| *start <= self.cursor_pos)
+            .map(|(i, (start, _))| (i, self.cursor_pos - start))
+            .unwrap_or_default()
+    }
+
+    fn insert(&mut self, ch: char) {
+        let i = self.byte_index(self.cursor_pos);
+        self.value.insert(i, ch);
+        self.cursor_pos += 1;
+    }
+
+    fn move_line(&mut self, up: bool) -> bool {
+        let lines = self.lines();
+        let (line, col) = self.cursor_line_col();
+        let target = match up {
+            true if line > 0 => line - 1,
+            false if line + 1 < lines.len() => line + 1,
+            _ => return false,
+        };
+        let (start, len) = lines[target];
+        self.cursor_pos = start + col.min(len);
+        true
+    }
+
+    /// Returns `true` when the key was consumed by the input
+    pub fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        match code {
+            KeyCode::Char(ch) if matches!(modifiers, KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                self.insert(ch);
+                true
+            }
+            KeyCode::Enter if self.multiline => {
+                self.insert('\n');
+                true
+            }
+            KeyCode::Backspace if self.cursor_pos > 0 => {
+                self.cursor_pos -= 1;
+                let i = self.byte_index(self.cursor_pos);
+                self.value.remove(i);
+                true
+            }
+            KeyCode::Delete if self.cursor_pos < self.value.chars().count() => {
+                let i = self.byte_index(self.cursor_pos);
+                self.value.remove(i);
+                true
+            }
+            KeyCode::Left if self.cursor_pos > 0 => {
+                self.cursor_pos -= 1;
+                true
+            }
+            KeyCode::Right if self.cursor_pos < self.value.chars().count() => {
+                self.cursor_pos += 1;
+                true
+            }
+            KeyCode::Home => {
+                let (_, col) = self.cursor_line_col();
+                self.cursor_pos -= col;
+                true
+            }
+            KeyCode::End => {
+                let (line, _) = self.cursor_line_col();
+                let (start, len) = self.lines()[line];
+                self.cursor_pos = start + len;
+                true
+            }
+            KeyCode::Up if self.multiline => self.move_line(true),
+            KeyCode::Down if self.multiline => self.move_line(false),
+            _ => false,
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        block: Block,
+        focused: bool,
+        area: Rect,
+        buf: &mut Buffer,
+    ) -> Option<Position> {
+        let inner = block.inner(area);
+        let (line, col) = self.cursor_line_col();
+        let (line, col) = (
+            u16::try_from(line).unwrap_or_default(),
+            u16::try_from(col).unwrap_or_default(),
+        );
+
+        if line < self.scroll.0 {
+            self.scroll.0 = line;
+        } else if inner.height > 0 && line >= self.scroll.0 + inner.height {
+            self.scroll.0 = line + 1 - inner.height;
+        }
+        if col < self.scroll.1 {
+            self.scroll.1 = col;
+        } else if inner.width > 0 && col >= self.scroll.1 + inner.width {
+            self.scroll.1 = col + 1 - inner.width;
+        }
+
+        Paragraph::new(self.value.as_str())
+            .scroll(self.scroll)
+            .block(block)
+            .render(area, buf);
+
+        focused.then(|| {
+            Position::new(
+                inner.x + col - self.scroll.1,
+                inner.y + line - self.scroll.0,
+            )
+        })
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use bookmarks_data::{Match, MatchField};
 use crossterm::{
     clipboard::CopyToClipboard,
     event::{KeyCode, KeyModifiers},
@@ -14,13 +15,26 @@ use ratatui::{
     },
 };
 
-use crate::tui::app::view::{edit::EditView, error::ErrorView};
+use crate::config::Key;
+use crate::tui::app::view::{
+    confirm::ConfirmView,
+    conflict::ConflictView,
+    diagnostics::DiagnosticsView,
+    duplicates::DuplicatesView,
+    edit::EditView,
+    error::ErrorView,
+    folders::{Folder, FolderTree, TreeEvent},
+    prompt::PromptView,
+    tags::TagsView,
+};
 use crate::tui::{
     app::{
         AppState,
+        state::ItemsChange,
         view::{EventState, View, statusline_help},
     },
     event::AppEvent,
+    search::SearchMode,
 };
 
 #[derive(Debug, Default)]
@@ -29,15 +43,76 @@ enum SelectedBlock {
     List,
     Content,
     Search,
+    Folders,
 }
 
 #[derive(Debug, Default)]
 struct Search {
+    mode: SearchMode,
     value: String,
     cursor_pos: usize,
-    items: Vec<(usize, i64)>,
+    items: Vec<(usize, Match)>,
     item_ids: HashMap<usize, usize>,
     latest_focused: usize,
+    /// Results of older queries are dropped
+    generation: u64,
+    /// Focuses the best match once the results of a new query arrive
+    focus_first: bool,
+}
+
+impl Search {
+    /// Re-runs the query in `value` in the background
+    fn run(&mut self, state: &mut AppState) {
+        self.generation = state.search(self.mode, &self.value);
+        self.focus_first = true;
+    }
+
+    fn update_ids(&mut self) {
+        self.item_ids = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i_local, (i_global, _score))| (*i_global, i_local))
+            .collect();
+    }
+
+    fn remove_item(&mut self, index: usize) {
+        self.items.retain(|it| it.0 != index);
+        for it in self.items.iter_mut().filter(|it| it.0 > index) {
+            it.0 -= 1;
+        }
+        self.update_ids();
+        self.latest_focused = self.latest_focused.min(self.items.len().saturating_sub(1));
+    }
+}
+
+/// Spans of `text` in `style`, the characters at `indices` stand out in `highlight`
+fn highlighted<'a>(
+    text: &'a str,
+    indices: &[usize],
+    style: Style,
+    highlight: Color,
+) -> Vec<Span<'a>> {
+    let matched_style = style.fg(highlight).bold();
+    let mut spans = vec![];
+    let mut start = 0;
+    let mut matched = false;
+    for (i, (offset, _)) in text.char_indices().enumerate() {
+        let is_match = indices.binary_search(&i).is_ok();
+        if is_match != matched {
+            if offset > start {
+                let style = if matched { matched_style } else { style };
+                spans.push(Span::styled(&text[start..offset], style));
+            }
+            start = offset;
+            matched = is_match;
+        }
+    }
+    if start < text.len() {
+        let style = if matched { matched_style } else { style };
+        spans.push(Span::styled(&text[start..], style));
+    }
+    spans
 }
 
 #[derive(Debug, Default)]
@@ -55,15 +130,146 @@ pub struct MainView {
     items_state: ListState,
     selected_content: Option<SelectedContent>,
     search: Option<Search>,
+    /// Folder pane, `None` while hidden
+    folders: Option<FolderTree>,
+    /// The list and search only show the items of this folder
+    scope: Option<Folder>,
+    /// List scroll offset while scoped, `items_state` holds it otherwise
+    scoped_offset: usize,
+}
+
+impl MainView {
+    /// Indices of the items in scope, all of them without one
+    fn visible(&self, state: &AppState) -> Vec<usize> {
+        match &self.scope {
+            Some(scope) => state
+                .items
+                .iter()
+                .enumerate()
+                .filter(|(_, it)| scope.contains(state, &it.content))
+                .map(|(i, _)| i)
+                .collect(),
+            None => (0..state.items.len()).collect(),
+        }
+    }
+
+    fn set_scope(&mut self, state: &mut AppState, scope: Option<Folder>) {
+        self.scope = scope;
+        self.scoped_offset = 0;
+        self.items_state
+            .select(self.visible(state).first().copied());
+        if let Some(search) = self.search.as_mut() {
+            search.run(state);
+        }
+    }
+
+    fn apply_changes(&mut self, state: &mut AppState) {
+        if let Some(value) = state.search_query.take() {
+            let mut search = Search {
+                cursor_pos: value.len(),
+                value,
+                ..Default::default()
+            };
+            search.run(state);
+            self.search = Some(search);
+            self.selected_block = SelectedBlock::List;
+        }
+        for change in state.changes.drain(..) {
+            match change {
+                ItemsChange::Removed(index) => {
+                    let selected = self
+                        .items_state
+                        .selected()
+                        .map(|i| if i > index { i - 1 } else { i })
+                        .filter(|_| !state.items.is_empty())
+                        .map(|i| i.min(state.items.len() - 1));
+                    self.items_state.select(selected);
+                    if let Some(selected_content) = self.selected_content.as_mut() {
+                        if selected_content.item_index == index {
+                            self.selected_content = None;
+                            self.selected_block = SelectedBlock::List;
+                        } else if selected_content.item_index > index {
+                            selected_content.item_index -= 1;
+                        }
+                    }
+                    if let Some(search) = self.search.as_mut() {
+                        search.remove_item(index);
+                    }
+                }
+            }
+        }
+        if let Some(results) = state.search_results.take()
+            && let Some(search) = self.search.as_mut()
+            && results.generation == search.generation
+            && results.removals == state.removals
+        {
+            let focused = search.items.get(search.latest_focused).map(|it| it.0);
+            search.items = results.items;
+            if let Some(scope) = &self.scope {
+                search.items.retain(|(i, _)| {
+                    state
+                        .items
+                        .get(*i)
+                        .is_some_and(|it| scope.contains(state, &it.content))
+                });
+            }
+            search.update_ids();
+            if std::mem::take(&mut search.focus_first) {
+                search.latest_focused = 0;
+                if let Some(it) = search.items.first() {
+                    self.items_state.select(Some(it.0));
+                }
+            } else {
+                // New items may rank above the focused one, it stays focused
+                search.latest_focused = focused
+                    .and_then(|it| search.item_ids.get(&it).copied())
+                    .unwrap_or_default();
+            }
+        }
+    }
 }
 
 impl View for MainView {
     fn handle_app_event(&mut self, state: &mut AppState, event: &AppEvent) -> EventState {
+        self.apply_changes(state);
+        // Remapped keys act as the built in ones, except while typing a search
+        let remapped;
+        let event = match event {
+            AppEvent::Key(code, modifiers)
+                if !matches!(self.selected_block, SelectedBlock::Search)
+                    && let Some(key) = state.keys.get(Key::new(*code, *modifiers)) =>
+            {
+                remapped = AppEvent::Key(key.code, key.modifiers);
+                &remapped
+            }
+            _ => event,
+        };
         if !state.items.is_empty() && self.items_state.selected().is_none() {
             self.items_state.select(Some(0));
         }
         let event_state = match self.selected_block {
             SelectedBlock::List => match event {
+                AppEvent::Key(KeyCode::Char(ch @ ('j' | 'k' | 'g' | 'G')), _)
+                    if self.scope.is_some() =>
+                {
+                    let visible = self.visible(state);
+                    let position = self
+                        .items_state
+                        .selected()
+                        .and_then(|i| visible.iter().position(|it| *it == i));
+                    let next = match (ch, position) {
+                        ('j', Some(p)) => visible.get(p + 1).or(visible.last()),
+                        ('k', Some(p)) => visible.get(p.saturating_sub(1)),
+                        ('G', _) => visible.last(),
+                        _ => visible.first(),
+                    };
+                    self.items_state.select(next.copied());
+                    EventState::Handled
+                }
+                AppEvent::Key(KeyCode::Char('h'), _) if self.folders.is_some() => {
+                    self.selected_block = SelectedBlock::Folders;
+                    EventState::Handled
+                }
                 AppEvent::Key(KeyCode::Char('k'), _) => {
                     self.items_state.select_previous();
                     EventState::Handled
@@ -103,22 +309,40 @@ impl View for MainView {
                     });
                     EventState::Handled
                 }
-                AppEvent::Key(KeyCode::Char('/'), _) => {
-                    self.search = Some(Default::default());
+                AppEvent::Key(KeyCode::Char(ch @ ('/' | '~')), _) => {
+                    self.search = Some(Search {
+                        mode: if *ch == '~' {
+                            SearchMode::Semantic
+                        } else {
+                            SearchMode::Fuzzy
+                        },
+                        ..Default::default()
+                    });
                     self.selected_block = SelectedBlock::Search;
                     EventState::Handled
                 }
                 AppEvent::Key(KeyCode::Esc, _) if self.search.is_some() => {
                     self.search = None;
+                    state.cancel_search();
                     EventState::Handled
                 }
-                AppEvent::Key(KeyCode::Char('n'), _) if let Some(search) = self.search.as_mut() => {
+                AppEvent::Key(KeyCode::Esc, _) if self.scope.is_some() => {
+                    self.set_scope(state, None);
+                    EventState::Handled
+                }
+                AppEvent::Key(KeyCode::Char('n'), _)
+                    if let Some(search) = self.search.as_mut()
+                        && !search.items.is_empty() =>
+                {
                     search.latest_focused = (search.latest_focused + 1) % search.items.len();
                     self.items_state
                         .select(search.items.get(search.latest_focused).map(|it| it.0));
                     EventState::Handled
                 }
-                AppEvent::Key(KeyCode::Char('N'), _) if let Some(search) = self.search.as_mut() => {
+                AppEvent::Key(KeyCode::Char('N'), _)
+                    if let Some(search) = self.search.as_mut()
+                        && !search.items.is_empty() =>
+                {
                     search.latest_focused = if search.latest_focused == 0 {
                         search.items.len() - 1
                     } else {
@@ -190,12 +414,14 @@ impl View for MainView {
                     if search.cursor_pos == 0 && search.value.is_empty() =>
                 {
                     self.search = None;
+                    state.cancel_search();
                     self.selected_block = SelectedBlock::List;
                     EventState::Handled
                 }
                 AppEvent::Key(KeyCode::Backspace, KeyModifiers::NONE) if search.cursor_pos > 0 => {
                     search.value.remove(search.cursor_pos - 1);
                     search.cursor_pos -= 1;
+                    search.run(state);
                     EventState::Handled
                 }
                 AppEvent::Key(KeyCode::End, _)
@@ -218,27 +444,23 @@ impl View for MainView {
                     search.cursor_pos -= 1;
                     EventState::Handled
                 }
+                AppEvent::Key(KeyCode::Tab, _) => {
+                    search.mode = match search.mode {
+                        SearchMode::Fuzzy => SearchMode::Semantic,
+                        SearchMode::Semantic => SearchMode::Fuzzy,
+                    };
+                    search.run(state);
+                    EventState::Handled
+                }
                 AppEvent::Key(KeyCode::Char(ch), _) => {
                     search.value.insert(search.cursor_pos, *ch);
                     search.cursor_pos += 1;
-                    search.items = bookmarks_data::search(
-                        &search.value,
-                        state.items.iter().map(|it| &it.content),
-                    )
-                    .collect();
-                    search.item_ids = search
-                        .items
-                        .iter()
-                        .enumerate()
-                        .map(|(i_local, (i_global, _score))| (*i_global, i_local))
-                        .collect();
-                    if let Some(it) = search.items.first() {
-                        self.items_state.select(Some(it.0));
-                    }
+                    search.run(state);
                     EventState::Handled
                 }
                 AppEvent::Key(KeyCode::Esc, _) => {
                     self.search = None;
+                    state.cancel_search();
                     self.selected_block = SelectedBlock::List;
                     EventState::Handled
                 }
@@ -260,6 +482,26 @@ impl View for MainView {
                 self.search = Some(Default::default());
                 EventState::NotHandled
             }
+            SelectedBlock::Folders if let Some(folders) = self.folders.as_mut() => match event {
+                AppEvent::Key(code, _) => match folders.handle_key(state, *code) {
+                    TreeEvent::Handled => EventState::Handled,
+                    TreeEvent::Choose(scope) => {
+                        self.set_scope(state, scope);
+                        self.selected_block = SelectedBlock::List;
+                        EventState::Handled
+                    }
+                    TreeEvent::NotHandled if *code == KeyCode::Esc => {
+                        self.selected_block = SelectedBlock::List;
+                        EventState::Handled
+                    }
+                    TreeEvent::NotHandled => EventState::NotHandled,
+                },
+                _ => EventState::NotHandled,
+            },
+            SelectedBlock::Folders => {
+                self.selected_block = SelectedBlock::List;
+                EventState::NotHandled
+            }
         };
         if !matches!(event_state, EventState::NotHandled) {
             return event_state;
@@ -282,7 +524,9 @@ impl View for MainView {
                         CopyToClipboard::to_clipboard_from(&item.content.url)
                     ) {
                         Ok(_) => EventState::Handled,
-                        Err(e) => EventState::PushBlockStack(Box::new(ErrorView(e.to_string()))),
+                        Err(e) => {
+                            EventState::PushBlockStack(Box::new(ErrorView::new(e.to_string())))
+                        }
                     }
                 } else {
                     EventState::NotHandled
@@ -290,9 +534,129 @@ impl View for MainView {
             }
             AppEvent::Key(KeyCode::Char('e'), _)
                 if let Some(selected_index) = self.items_state.selected()
-                    && selected_index < state.items.len() =>
+                    && let Some(item) = state.items.get(selected_index) =>
+            {
+                EventState::PushStack(Box::new(EditView::new(item)))
+            }
+            AppEvent::Key(KeyCode::Char('E'), _)
+                if let Some(selected_index) = self.items_state.selected()
+                    && let Some(item) = state.items.get(selected_index) =>
+            {
+                state.open_in_editor = Some(item.path.clone());
+                EventState::Handled
+            }
+            AppEvent::Key(KeyCode::Char('a'), _) => {
+                let folder = self
+                    .items_state
+                    .selected()
+                    .and_then(|i| state.items.get(i))
+                    .map(|it| state.folder_label(&it.content))
+                    .unwrap_or_default();
+                EventState::PushStack(Box::new(EditView::create(folder)))
+            }
+            AppEvent::Key(KeyCode::Char('C'), _)
+                if state
+                    .sync_status
+                    .as_ref()
+                    .is_some_and(|it| it.rebasing || !it.conflicts.is_empty()) =>
+            {
+                match ConflictView::open(&state.data) {
+                    Ok(view) => EventState::PushStack(Box::new(view)),
+                    Err(e) => EventState::PushStack(Box::new(ErrorView::new(e.to_string()))),
+                }
+            }
+            AppEvent::Key(KeyCode::Char('f'), _) => {
+                match (self.folders.is_some(), &self.selected_block) {
+                    (true, SelectedBlock::Folders) => {
+                        self.folders = None;
+                        self.selected_block = SelectedBlock::List;
+                    }
+                    (true, _) => self.selected_block = SelectedBlock::Folders,
+                    (false, _) => {
+                        self.folders = Some(FolderTree::default());
+                        self.selected_block = SelectedBlock::Folders;
+                    }
+                }
+                EventState::Handled
+            }
+            AppEvent::Key(KeyCode::Char('t'), _) => {
+                EventState::PushStack(Box::new(TagsView::default()))
+            }
+            AppEvent::Key(KeyCode::Char('D'), _) => {
+                EventState::PushStack(Box::new(DuplicatesView::default()))
+            }
+            AppEvent::Key(KeyCode::Char('!'), _) => {
+                EventState::PushStack(Box::new(DiagnosticsView::default()))
+            }
+            AppEvent::Key(KeyCode::Char('d'), _)
+                if let Some(selected_index) = self.items_state.selected()
+                    && let Some(item) = state.items.get(selected_index) =>
+            {
+                let path = item.path.clone();
+                EventState::PushStack(Box::new(ConfirmView::new(
+                    format!("Delete {}?", item.relative_path.to_string_lossy()),
+                    move |state| {
+                        if let Some(index) = state.position_of(&path) {
+                            bookmarks_data::delete_from_fs(&state.items[index])?;
+                            state.remove_item(index);
+                        }
+                        Ok(())
+                    },
+                )))
+            }
+            AppEvent::Key(KeyCode::Char('m'), _)
+                if let Some(selected_index) = self.items_state.selected()
+                    && let Some(item) = state.items.get(selected_index) =>
+            {
+                let mut path = item.path.clone();
+                let prompt = match state.collections.len() {
+                    1 => "Move to folder (slash separated)",
+                    _ => "Move to folder (collection:folder/sub)",
+                };
+                EventState::PushStack(Box::new(PromptView::new(
+                    prompt,
+                    state.folder_label(&item.content),
+                    move |state, value| {
+                        let Some(index) = state.position_of(&path) else {
+                            return Ok(());
+                        };
+                        let current = state.items[index].content.collection.clone();
+                        let (collection, folder) = state.parse_folder(value, current.as_deref())?;
+                        let item = &mut state.items[index];
+                        if current.is_none_or(|it| it == collection.name) {
+                            bookmarks_data::move_in_fs(item, &folder)?;
+                        } else {
+                            bookmarks_data::move_to_root(item, &collection.path, &folder)?;
+                            item.content.collection = Some(collection.name);
+                        }
+                        path = item.path.clone();
+                        state.item_changed(index);
+                        Ok(())
+                    },
+                )))
+            }
+            AppEvent::Key(KeyCode::Char('r'), _)
+                if let Some(selected_index) = self.items_state.selected()
+                    && let Some(item) = state.items.get(selected_index) =>
             {
-                EventState::PushStack(Box::new(EditView(Some(selected_index))))
+                let mut path = item.path.clone();
+                let stem = item
+                    .path
+                    .file_stem()
+                    .map(|it| it.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                EventState::PushStack(Box::new(PromptView::new(
+                    "Rename file",
+                    stem,
+                    move |state, value| {
+                        if let Some(index) = state.position_of(&path) {
+                            bookmarks_data::rename_in_fs(&mut state.items[index], value)?;
+                            state.item_changed(index);
+                            path = state.items[index].path.clone();
+                        }
+                        Ok(())
+                    },
+                )))
             }
             _ => EventState::NotHandled,
         }
@@ -302,7 +666,7 @@ impl View for MainView {
         &mut self,
         area: Rect,
         buf: &mut Buffer,
-        _state: &mut AppState,
+        state: &mut AppState,
     ) -> Option<Position> {
         let content_area = if !matches!(self.selected_block, SelectedBlock::Search)
             && let Some(search) = self.search.as_ref()
@@ -320,7 +684,7 @@ impl View for MainView {
             ])
             .split(area);
 
-            Text::styled(hint, Style::new().fg(Color::Blue)).render(layout[2], buf);
+            Text::styled(hint, Style::new().fg(state.theme.help)).render(layout[2], buf);
             layout[0]
         } else {
             area
@@ -329,39 +693,56 @@ impl View for MainView {
         match self.selected_block {
             SelectedBlock::List if self.selected_content.is_some() => {
                 statusline_help(
-                    "Quit: q | Next: j | Prev: k | Open: o | Copy url: y | Select: return | Focus Select: space | Search: / | Focus Content: l",
+                    state.keys.describe("Quit: q | Next: j | Prev: k | Open: o | Copy url: y | Edit: e | $EDITOR: E | Add: a | Move: m | Rename: r | Delete: d | Select: return | Focus Select: space | Search: / | Semantic: ~ | Tags: t | Duplicates: D | Folders: f | Focus Content: l"),
                     content_area,
                     buf,
+                    &state.theme,
                 );
             }
             SelectedBlock::List => {
                 statusline_help(
-                    "Quit: q | Next: j | Prev: k | Open: o | Copy url: y | Select: return | Focus Select: space | Search: /",
+                    state.keys.describe("Quit: q | Next: j | Prev: k | Open: o | Copy url: y | Edit: e | $EDITOR: E | Add: a | Move: m | Rename: r | Delete: d | Select: return | Focus Select: space | Search: / | Semantic: ~ | Tags: t | Duplicates: D | Folders: f"),
                     content_area,
                     buf,
+                    &state.theme,
                 );
             }
             SelectedBlock::Content => {
                 statusline_help(
-                    "Quit: q | Open: o | Copy url: y | Focus List: h | Up: k | Down: j | Close: esc",
+                    state.keys.describe("Quit: q | Open: o | Copy url: y | Edit: e | $EDITOR: E | Focus List: h | Up: k | Down: j | Close: esc"),
                     content_area,
                     buf,
+                    &state.theme,
                 );
             }
             SelectedBlock::Search if let Some(search) = self.search.as_ref() => {
-                Paragraph::new(format!("/{}", search.value)).render(content_area, buf);
+                let [prompt_area, help_area] =
+                    Layout::horizontal([Constraint::Fill(1), Constraint::Length(22)])
+                        .areas(content_area);
+                Paragraph::new(format!("{}{}", search.mode.prompt(), search.value))
+                    .render(prompt_area, buf);
+                statusline_help("Fuzzy/semantic: tab", help_area, buf, &state.theme);
                 return Some(Position::new(
                     content_area.x + u16::try_from(search.cursor_pos).unwrap_or_default() + 1,
                     content_area.y,
                 ));
             }
             SelectedBlock::Search => {}
+            SelectedBlock::Folders => {
+                statusline_help(
+                    state.keys.describe("Hide: f | Next: j | Prev: k | Expand: l | Collapse: h | Toggle: space | Show Folder: return | Focus List: esc"),
+                    content_area,
+                    buf,
+                    &state.theme,
+                );
+            }
         };
         None
     }
 
     fn render(&mut self, area: Rect, buf: &mut Buffer, state: &mut AppState) -> Option<Position> {
-        let selected_block_style = Style::new().fg(Color::Yellow);
+        self.apply_changes(state);
+        let selected_block_style = Style::new().fg(state.theme.accent);
 
         let list_area = if let Some(selected_content) = self.selected_content.as_mut()
             && let Some(record_file) = state.items.get(selected_content.item_index)
@@ -378,29 +759,59 @@ impl View for MainView {
                     content_style.title(Line::from(title.clone().bold()).left_aligned());
             }
             if let Some(relative_path) = record_file.relative_path.to_str() {
-                content_style =
-                    content_style.title(Line::from(relative_path.dim().gray()).right_aligned());
+                content_style = content_style
+                    .title(Line::from(relative_path.dim().fg(state.theme.folder)).right_aligned());
             }
 
+            let found = self.search.as_ref().and_then(|search| {
+                let local_i = search.item_ids.get(&selected_content.item_index)?;
+                Some(&search.items.get(*local_i)?.1.highlights)
+            });
+            let highlights = |field| found.map(|it| it.get(field)).unwrap_or_default();
+
             let mut text = Text::default();
-            text.push_line(Line::from(
-                record_file.content.url.clone().blue().underlined(),
-            ));
+            text.push_line(Line::from(highlighted(
+                &record_file.content.url,
+                highlights(MatchField::Url),
+                Style::new().fg(state.theme.url).underlined(),
+                state.theme.highlight,
+            )));
 
             if let Some(tags) = &record_file.content.tags {
                 let mut line = Line::from("Tags:");
-                for tag in tags {
+                for (i, tag) in tags.iter().enumerate() {
                     line.push_span(" ");
-                    line.push_span(" ".on_dark_gray());
-                    line.push_span(tag.clone().dark_gray().reversed());
-                    line.push_span(" ".on_dark_gray());
+                    line.push_span(" ".bg(state.theme.muted));
+                    for span in highlighted(
+                        tag,
+                        highlights(MatchField::Tag(i)),
+                        Style::new().fg(state.theme.muted).reversed(),
+                        state.theme.highlight,
+                    ) {
+                        line.push_span(span);
+                    }
+                    line.push_span(" ".bg(state.theme.muted));
                 }
                 text.push_line(line);
             }
 
             if let Some(description) = &record_file.content.description {
-                for line in description.lines() {
-                    text.push_line(line);
+                // Highlights are character indices into the whole description
+                let indices = highlights(MatchField::Description);
+                let mut offset = 0;
+                for line in description.split('\n') {
+                    let local: Vec<usize> = indices
+                        .iter()
+                        .filter_map(|i| i.checked_sub(offset))
+                        .collect();
+                    offset += line.chars().count() + 1;
+                    let line = line.strip_suffix('\r').unwrap_or(line);
+                    text.push_line(Line::from(highlighted(
+                        line,
+                        &local,
+                        Style::new(),
+                        state.theme.highlight,
+                    )));
                 }
             }
 
@@ -438,13 +849,51 @@ impl View for MainView {
             area
         };
 
+        let list_area = match self.folders.as_mut() {
+            Some(folders) => {
+                let [tree_area, list_area] =
+                    Layout::horizontal([Constraint::Percentage(25), Constraint::Fill(1)])
+                        .areas(list_area);
+                let focused = matches!(self.selected_block, SelectedBlock::Folders);
+                folders.render(tree_area, buf, state, focused, self.scope.as_ref());
+                list_area
+            }
+            None => list_area,
+        };
+
+        let visible = self.visible(state);
         let mut list_title = vec![Span::raw("Bookmarks")];
+        if let Some(scope) = &self.scope {
+            list_title.push(Span::styled(
+                format!(" in {} ({})", scope.label(), visible.len()),
+                Style::new().fg(state.theme.accent),
+            ));
+        }
         if !state.items_loaded {
             list_title.push(Span::styled(
                 format!(" {} ", state.loader),
                 Style::new().dim(),
             ));
         }
+        if !state.diagnostics.is_empty() {
+            list_title.push(Span::styled(
+                format!(" [{} broken, show: !] ", state.diagnostics.len()),
+                Style::new().fg(state.theme.error),
+            ));
+        }
+        if let Some(sync_status) = state.sync_status.as_ref() {
+            let style = if !sync_status.conflicts.is_empty() {
+                Style::new().fg(state.theme.error)
+            } else if sync_status.changed > 0 || sync_status.ahead > 0 || sync_status.behind > 0 {
+                Style::new().fg(state.theme.warning)
+            } else {
+                Style::new().dim()
+            };
+            list_title.push(Span::styled(format!(" [git: {sync_status}] "), style));
+            if sync_status.rebasing {
+                list_title.push(Span::styled("Resolve: C ", style));
+            }
+        }
 
         let mut list_block = Block::bordered()
             .border_type(BorderType::Rounded)
@@ -452,51 +901,94 @@ impl View for MainView {
         if matches!(self.selected_block, SelectedBlock::List) {
             list_block = list_block.border_style(selected_block_style);
         }
-        let list = state
-            .items
+        let list = visible
             .iter()
-            .enumerate()
-            .map(|(i, it)| {
+            .map(|&i| {
+                let it = &state.items[i];
                 let mut text = Text::default();
                 let mut title_line = Line::default();
+                let found = self
+                    .search
+                    .as_ref()
+                    .and_then(|search| Some((search, *search.item_ids.get(&i)?)))
+                    .and_then(|(search, local_i)| Some((local_i, &search.items.get(local_i)?.1)));
+                let highlights = |field| {
+                    found
+                        .map(|(_, found)| found.highlights.get(field))
+                        .unwrap_or_default()
+                };
+                if state.collections.len() > 1
+                    && let Some(collection) = &it.content.collection
+                {
+                    title_line.push_span(format!("[{collection}] ").fg(state.theme.accent));
+                }
                 for (i, part) in it.content.path.iter().enumerate() {
                     if i > 0 {
-                        title_line.push_span("/".gray().dim());
+                        title_line.push_span("/".fg(state.theme.folder).dim());
+                    }
+                    for span in highlighted(
+                        part,
+                        highlights(MatchField::Path(i)),
+                        Style::new().fg(state.theme.folder),
+                        state.theme.highlight,
+                    ) {
+                        title_line.push_span(span);
                     }
-                    title_line.push_span(part.clone().gray());
                 }
                 if !it.content.path.is_empty() {
-                    title_line.push_span(" :".gray().dim());
+                    title_line.push_span(" :".fg(state.theme.folder).dim());
                     title_line.push_span(" ");
                 }
                 if let Some(title) = it.content.title.as_ref() {
-                    title_line.push_span(title);
+                    for span in highlighted(
+                        title,
+                        highlights(MatchField::Title),
+                        Style::new(),
+                        state.theme.highlight,
+                    ) {
+                        title_line.push_span(span);
+                    }
                 }
                 if let Some(search) = self.search.as_ref() {
-                    if let Some(local_i) = search.item_ids.get(&i)
-                        && let Some((_, score)) = search.items.get(*local_i)
-                    {
+                    if let Some((local_i, found)) = found {
                         title_line.push_span(" ");
                         title_line.push_span(
-                            format!("[{}/{}]", local_i + 1, search.item_ids.len()).blue(),
+                            format!("[{}/{}]", local_i + 1, search.item_ids.len())
+                                .fg(state.theme.info),
                         );
                         title_line.push_span(" ");
-                        title_line.push_span(format!("[score: {score}]").blue());
+                        title_line
+                            .push_span(format!("[score: {}]", found.score).fg(state.theme.info));
                     } else {
                         text = text.dim();
                     }
                 };
                 text.push_line(title_line);
-                text.push_line(
-                    Span::styled(&it.content.url, Style::new().fg(Color::DarkGray))
-                        .into_left_aligned_line(),
-                );
+                text.push_line(Line::from(highlighted(
+                    &it.content.url,
+                    highlights(MatchField::Url),
+                    Style::new().fg(state.theme.muted),
+                    state.theme.highlight,
+                )));
                 text
             })
             .collect::<List>()
             .block(list_block)
             .highlight_style(Style::new().reversed());
-        StatefulWidget::render(list, list_area, buf, &mut self.items_state);
+        match &self.scope {
+            Some(_) => {
+                let selected = self
+                    .items_state
+                    .selected()
+                    .and_then(|i| visible.iter().position(|it| *it == i));
+                let mut list_state = ListState::default()
+                    .with_offset(self.scoped_offset)
+                    .with_selected(selected);
+                StatefulWidget::render(list, list_area, buf, &mut list_state);
+                self.scoped_offset = list_state.offset();
+            }
+            None => StatefulWidget::render(list, list_area, buf, &mut self.items_state),
+        }
 
         None
     }
@@ -1,17 +1,30 @@
 use ratatui::prelude::*;
 
-use crate::tui::{app::state::AppState, event::AppEvent};
+use crate::{
+    config::Theme,
+    tui::{app::state::AppState, event::AppEvent},
+};
 
+pub mod confirm;
+pub mod conflict;
+pub mod diagnostics;
+pub mod duplicates;
 pub mod edit;
 pub mod error;
+pub mod folders;
+pub mod input;
 pub mod loader;
 pub mod main;
+pub mod prompt;
+pub mod tags;
 
 pub type ViewBoxed = Box<dyn View>;
 pub enum EventState {
     Handled,
     PushStack(ViewBoxed),
     PushBlockStack(ViewBoxed),
+    PopStack,
+    ReplaceStack(ViewBoxed),
     NotHandled,
 }
 
@@ -27,8 +40,8 @@ pub trait View {
 }
 
 #[inline]
-pub fn statusline_help<'a>(text: impl Into<Text<'a>>, area: Rect, buf: &mut Buffer) {
+pub fn statusline_help<'a>(text: impl Into<Text<'a>>, area: Rect, buf: &mut Buffer, theme: &Theme) {
     text.into()
-        .style(Style::new().dim().bold().blue())
+        .style(Style::new().dim().bold().fg(theme.help))
         .render(area, buf);
 }
@@ -0,0 +1,99 @@
+use std::io;
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Clear, Paragraph},
+};
+
+use crate::tui::{
+    app::{
+        state::AppState,
+        view::{EventState, View, input::TextInput, statusline_help},
+    },
+    event::AppEvent,
+};
+
+type PromptAction = Box<dyn FnMut(&mut AppState, &str) -> io::Result<()>>;
+
+/// Single line input popup, submitted with return
+pub struct PromptView {
+    title: String,
+    input: TextInput,
+    action: PromptAction,
+    error: Option<String>,
+}
+
+impl PromptView {
+    pub fn new(
+        title: impl Into<String>,
+        value: impl Into<String>,
+        action: impl FnMut(&mut AppState, &str) -> io::Result<()> + 'static,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            input: TextInput::new(value),
+            action: Box::new(action),
+            error: None,
+        }
+    }
+}
+
+impl View for PromptView {
+    fn handle_app_event(&mut self, state: &mut AppState, event: &AppEvent) -> EventState {
+        let AppEvent::Key(code, modifiers) = event else {
+            return EventState::NotHandled;
+        };
+        match code {
+            KeyCode::Esc => EventState::PopStack,
+            KeyCode::Enter => match (self.action)(state, self.input.value()) {
+                Ok(()) => EventState::PopStack,
+                Err(err) => {
+                    self.error = Some(err.to_string());
+                    EventState::Handled
+                }
+            },
+            _ if self.input.handle_key(*code, *modifiers) => {
+                self.error = None;
+                EventState::Handled
+            }
+            _ => EventState::NotHandled,
+        }
+    }
+
+    fn render_statusline(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &mut AppState,
+    ) -> Option<Position> {
+        statusline_help(
+            "Submit: return | Cancel: esc | Quit Application: c-q",
+            area,
+            buf,
+            &state.theme,
+        );
+        None
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, state: &mut AppState) -> Option<Position> {
+        let popup_area = Rect {
+            x: area.width / 4,
+            y: area.height / 3,
+            width: area.width / 2,
+            height: 4.min(area.height),
+        };
+        Clear.render(popup_area, buf);
+        let layout =
+            Layout::vertical([Constraint::Length(3), Constraint::Length(1)]).split(popup_area);
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::new().fg(state.theme.accent))
+            .title(self.title.as_str());
+        let cursor = self.input.render(block, true, layout[0], buf);
+        if let Some(error) = self.error.as_deref() {
+            Paragraph::new(error.fg(state.theme.error)).render(layout[1], buf);
+        }
+        cursor
+    }
+}
@@ -0,0 +1,167 @@
+use crossterm::event::KeyCode;
+use itertools::Itertools;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Clear, List, ListState, Paragraph},
+};
+
+use crate::tui::{
+    app::{
+        state::AppState,
+        view::{EventState, View, statusline_help},
+    },
+    event::AppEvent,
+};
+
+/// Tags of the loaded items, filters the main list by the chosen ones
+#[derive(Debug, Default)]
+pub struct TagsView {
+    list_state: ListState,
+    chosen: Vec<String>,
+}
+
+/// Search matching the items carrying every tag
+fn tags_query(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| {
+            if tag
+                .chars()
+                .all(|it| it.is_alphanumeric() || "-_.".contains(it))
+            {
+                format!("tag:{tag}")
+            } else {
+                format!("tag:\"{}\"", tag.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+        })
+        .join(" ")
+}
+
+impl View for TagsView {
+    fn handle_app_event(&mut self, state: &mut AppState, event: &AppEvent) -> EventState {
+        match event {
+            AppEvent::Key(KeyCode::Char('j') | KeyCode::Down, _) => {
+                self.list_state.select_next();
+                EventState::Handled
+            }
+            AppEvent::Key(KeyCode::Char('k') | KeyCode::Up, _) => {
+                self.list_state.select_previous();
+                EventState::Handled
+            }
+            AppEvent::Key(KeyCode::Char(' '), _) => {
+                let counts = bookmarks_data::tag_counts(state.items.iter().map(|it| &it.content));
+                if let Some((tag, _)) = self.list_state.selected().and_then(|i| counts.get(i)) {
+                    match self.chosen.iter().position(|it| it == tag) {
+                        Some(i) => {
+                            self.chosen.remove(i);
+                        }
+                        None => self.chosen.push(tag.clone()),
+                    }
+                }
+                EventState::Handled
+            }
+            AppEvent::Key(KeyCode::Enter, _) => {
+                let mut tags = self.chosen.clone();
+                if tags.is_empty() {
+                    let counts =
+                        bookmarks_data::tag_counts(state.items.iter().map(|it| &it.content));
+                    match self.list_state.selected().and_then(|i| counts.get(i)) {
+                        Some((tag, _)) => tags.push(tag.clone()),
+                        None => return EventState::PopStack,
+                    }
+                }
+                state.search_query = Some(tags_query(&tags));
+                EventState::PopStack
+            }
+            AppEvent::Key(KeyCode::Esc, _) => EventState::PopStack,
+            _ => EventState::NotHandled,
+        }
+    }
+
+    fn render_statusline(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &mut AppState,
+    ) -> Option<Position> {
+        statusline_help(
+            "Close: esc | Next: j | Prev: k | Choose: space | Filter: return | Quit Application: c-q",
+            area,
+            buf,
+            &state.theme,
+        );
+        None
+    }
+
+    fn render(&mut self, area: Rect, buf: &mut Buffer, state: &mut AppState) -> Option<Position> {
+        let popup_area = area.inner(Margin {
+            horizontal: area.width / 4,
+            vertical: area.height / 8,
+        });
+        Clear.render(popup_area, buf);
+        let counts = bookmarks_data::tag_counts(state.items.iter().map(|it| &it.content));
+        let mut title = format!("Tags ({})", counts.len());
+        if !self.chosen.is_empty() {
+            title.push_str(&format!(", {} chosen", self.chosen.len()));
+        }
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::new().fg(state.theme.accent))
+            .title(Line::from(title.bold()).left_aligned());
+
+        if counts.is_empty() {
+            Paragraph::new("No tags".dim())
+                .block(block)
+                .render(popup_area, buf);
+            return None;
+        }
+        if self.list_state.selected().is_none() {
+            self.list_state.select_first();
+        }
+
+        let list = counts
+            .iter()
+            .map(|(tag, count)| {
+                let chosen = self.chosen.contains(tag);
+                Line::from(vec![
+                    if chosen { "[x] " } else { "[ ] " }.into(),
+                    match chosen {
+                        true => tag.clone().fg(state.theme.accent).bold(),
+                        false => tag.clone().into(),
+                    },
+                    format!(" {count}").dim(),
+                ])
+            })
+            .collect::<List>()
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        StatefulWidget::render(list, popup_area, buf, &mut self.list_state);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bookmarks_data::{BookmarkRecord, Query};
+
+    use super::*;
+
+    #[test]
+    fn tags_query_quotes_and_escapes() {
+        let tags = ["rust", "c++", "say \"hi\"", "a\\b"].map(str::to_string);
+        assert_eq!(
+            tags_query(&tags),
+            r#"tag:rust tag:"c++" tag:"say \"hi\"" tag:"a\\b""#
+        );
+
+        let record = |tags: &[String]| BookmarkRecord {
+            tags: Some(tags.to_vec()),
+            ..Default::default()
+        };
+        let query = Query::parse(&tags_query(&tags));
+        assert!(query.matches(&record(&tags)));
+        assert!(!query.matches(&record(&tags[..3])));
+        for tag in &tags {
+            assert!(Query::parse(&tags_query(std::slice::from_ref(tag))).matches(&record(&tags)));
+        }
+    }
+}
@@ -1,53 +1,219 @@
-use std::{io, path::PathBuf};
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use bookmarks_data::BookmarkFile;
-use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+use bookmarks_data::{BookmarkFile, Diagnostic, DiagnosticKind, IndexedFile, TomlFileIterator};
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{DebounceEventResult, Debouncer};
+use tokio::{
+    sync::mpsc::{self, UnboundedSender},
+    task::JoinHandle,
+};
+
+use crate::config::Collection;
+
+/// Quiet time before a burst of file system events is reported, e.g. a git pull
+const WATCH_DELAY: Duration = Duration::from_millis(300);
 
 #[derive(Debug)]
 pub struct DataWorker {
     tx: UnboundedSender<DataEvent>,
-    loader: Option<JoinHandle<color_eyre::Result<()>>>,
+    /// One per collection
+    loaders: Vec<JoinHandle<color_eyre::Result<()>>>,
 }
 
 #[derive(Debug)]
 pub enum DataEvent {
-    NewFile(BookmarkFile),
+    NewFile(Box<IndexedFile>),
+    Diagnostic(Diagnostic),
+    /// A collection is loaded, sent once per collection
     Loaded,
-    LoadError(io::Error),
+    /// A collection failed to load, instead of [`DataEvent::Loaded`]
+    LoadError(String, io::Error),
+    /// A file created on disk after loading
+    FileAdded(Box<BookmarkFile>),
+    /// A loaded file changed on disk
+    FileChanged(Box<BookmarkFile>),
+    /// Path of a loaded file that is gone from disk
+    FileRemoved(PathBuf),
+}
+
+/// Files of a collection known to the app, turns watcher events into [`DataEvent`]s
+#[derive(Debug)]
+struct Watched {
+    collection: String,
+    data: PathBuf,
+    /// Canonical data directory, the watcher reports paths below it
+    root: PathBuf,
+    /// Relative paths
+    known: HashSet<PathBuf>,
+}
+
+fn is_hidden(relative_path: &Path) -> bool {
+    relative_path
+        .iter()
+        .any(|it| it.to_string_lossy().starts_with('.'))
+}
+
+impl Watched {
+    fn changes(&mut self, paths: Vec<PathBuf>) -> Vec<DataEvent> {
+        let mut events = vec![];
+        for path in paths {
+            let Ok(relative_path) = path.strip_prefix(&self.root) else {
+                continue;
+            };
+            if is_hidden(relative_path) {
+                continue;
+            }
+            if path.is_dir() {
+                // Files moved in with their folder may come without events of their own
+                for entry in TomlFileIterator::new(&path).into_iter().flatten().flatten() {
+                    events.push(self.reload(relative_path.join(entry.relative_path)));
+                }
+            } else if path.is_file() {
+                if path.extension().is_some_and(|it| it == "toml") {
+                    events.push(self.reload(relative_path.to_path_buf()));
+                }
+            } else {
+                let removed: Vec<_> = self
+                    .known
+                    .iter()
+                    .filter(|it| it.starts_with(relative_path))
+                    .cloned()
+                    .collect();
+                for it in removed {
+                    self.known.remove(&it);
+                    events.push(DataEvent::FileRemoved(self.data.join(it)));
+                }
+            }
+        }
+        events
+    }
+
+    fn reload(&mut self, relative_path: PathBuf) -> DataEvent {
+        let loaded = bookmarks_data::load_file(&self.data, &relative_path).map(|mut file| {
+            file.content.collection = Some(self.collection.clone());
+            file
+        });
+        match loaded {
+            Ok(file) if self.known.insert(relative_path.clone()) => {
+                DataEvent::FileAdded(Box::new(file))
+            }
+            Ok(file) => DataEvent::FileChanged(Box::new(file)),
+            Err(e) => {
+                let kind = match e.kind() {
+                    io::ErrorKind::InvalidData => DiagnosticKind::Parse,
+                    _ => DiagnosticKind::Read,
+                };
+                DataEvent::Diagnostic(Diagnostic::new(self.data.join(relative_path), kind, &e))
+            }
+        }
+    }
+}
+
+/// Reports the paths below `root` that changed, recursively
+fn watch(
+    root: &Path,
+    tx: UnboundedSender<DebounceEventResult>,
+) -> notify::Result<Debouncer<RecommendedWatcher>> {
+    let mut debouncer = notify_debouncer_mini::new_debouncer(WATCH_DELAY, move |result| {
+        let _ = tx.send(result);
+    })?;
+    debouncer.watcher().watch(root, RecursiveMode::Recursive)?;
+    Ok(debouncer)
 }
 
 impl DataWorker {
     pub fn new(tx: UnboundedSender<DataEvent>) -> Self {
-        Self { tx, loader: None }
+        Self {
+            tx,
+            loaders: vec![],
+        }
+    }
+
+    /// Loads the collections concurrently, then keeps watching them for changes made outside the app
+    pub fn load_items(&mut self, collections: Vec<Collection>) {
+        for collection in collections {
+            let handle = tokio::spawn(Self::load_collection(self.tx.clone(), collection));
+            self.loaders.push(handle);
+        }
     }
 
-    pub fn load_items(&mut self, data: PathBuf) {
-        let tx = self.tx.clone();
+    async fn load_collection(
+        tx: UnboundedSender<DataEvent>,
+        collection: Collection,
+    ) -> color_eyre::Result<()> {
+        let Collection { name, path: data } = collection;
+        // Watching starts before loading, so changes made meanwhile are not lost
+        let (w_tx, mut w_rx) = mpsc::unbounded_channel();
+        let watcher = data
+            .canonicalize()
+            .and_then(|root| Ok((watch(&root, w_tx).map_err(io::Error::other)?, root)));
 
-        let handle = tokio::spawn(async move {
-            match bookmarks_data::load_from_fs(data) {
-                Ok(iter) => {
-                    for file in iter {
-                        tx.send(DataEvent::NewFile(file))?;
-                    }
-                    tx.send(DataEvent::Loaded)?;
+        let load_data = data.clone();
+        let mut known = HashSet::new();
+        match tokio::task::spawn_blocking(move || bookmarks_data::load_indexed(load_data)).await? {
+            Ok(load) => {
+                for mut file in load.files {
+                    known.insert(file.file.relative_path.clone());
+                    file.file.content.collection = Some(name.clone());
+                    tx.send(DataEvent::NewFile(Box::new(file)))?;
                 }
-                Err(err) => {
-                    tx.send(DataEvent::LoadError(err))?;
+                for diagnostic in load.diagnostics {
+                    tx.send(DataEvent::Diagnostic(diagnostic))?;
                 }
+                tx.send(DataEvent::Loaded)?;
             }
-            Ok(())
-        });
-        self.loader = Some(handle);
+            Err(err) => {
+                tx.send(DataEvent::LoadError(name, err))?;
+                return Ok(());
+            }
+        }
+
+        let (_watcher, root) = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                tracing::warn!("Not watching {data:?} for changes. {e}");
+                return Ok(());
+            }
+        };
+        let mut watched = Watched {
+            collection: name,
+            root,
+            data,
+            known,
+        };
+        while let Some(result) = w_rx.recv().await {
+            let paths = match result {
+                Ok(events) => events.into_iter().map(|it| it.path).collect(),
+                Err(e) => {
+                    tracing::warn!("Watching for changes failed. {e}");
+                    continue;
+                }
+            };
+            let events;
+            (watched, events) = tokio::task::spawn_blocking(move || {
+                let events = watched.changes(paths);
+                (watched, events)
+            })
+            .await?;
+            for event in events {
+                tx.send(event)?;
+            }
+        }
+        Ok(())
     }
 }
 
 impl Drop for DataWorker {
     fn drop(&mut self) {
-        if let Some(loader) = &self.loader
-            && !loader.is_finished()
-        {
-            loader.abort();
+        for loader in &self.loaders {
+            if !loader.is_finished() {
+                loader.abort();
+            }
         }
     }
 }
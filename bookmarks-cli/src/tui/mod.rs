@@ -1,14 +1,22 @@
-use std::path::PathBuf;
-
-use crate::tui::app::App;
+use crate::{
+    config::{Collection, Config},
+    tui::app::App,
+};
 
 mod app;
 mod data;
 mod event;
+mod search;
+mod sync;
 mod terminal_events;
 
 #[tokio::main]
-pub async fn run(data: PathBuf) -> color_eyre::Result<()> {
+/// Opens the interactive interface, on top of it the duplicates when `duplicates` is set
+pub async fn run(
+    collections: Vec<Collection>,
+    config: Config,
+    duplicates: bool,
+) -> color_eyre::Result<()> {
     let app = App::try_new()?;
-    app.run(data).await
+    app.run(collections, config, duplicates).await
 }
@@ -0,0 +1,188 @@
+use std::{
+    sync::{Arc, mpsc},
+    time::{Duration, Instant},
+};
+
+use bookmarks_data::{BookmarkRecord, Embedder, Match, Query, RecordTokens, SearchIndex};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+
+/// How long new items are collected before the active search runs again
+const BATCH_DELAY: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    #[default]
+    Fuzzy,
+    /// Embedding similarity, only finds embedded records
+    Semantic,
+}
+
+impl SearchMode {
+    pub fn prompt(self) -> char {
+        match self {
+            SearchMode::Fuzzy => '/',
+            SearchMode::Semantic => '~',
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub generation: u64,
+    pub mode: SearchMode,
+    pub value: String,
+}
+
+/// Mirrors the changes of `AppState.items`, plus the query to keep running
+#[derive(Debug)]
+pub enum SearchRequest {
+    Push(Box<BookmarkRecord>, Option<Box<RecordTokens>>),
+    Update(usize, Box<BookmarkRecord>),
+    Remove(usize),
+    Query(Option<SearchQuery>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    pub generation: u64,
+    /// Removed items the results account for, older results point to shifted items
+    pub removals: u64,
+    pub items: Vec<(usize, Match)>,
+}
+
+#[derive(Debug)]
+pub struct SearchWorker {
+    tx: UnboundedSender<SearchResults>,
+    searcher: Option<JoinHandle<color_eyre::Result<()>>>,
+}
+
+#[derive(Debug, Default)]
+struct Searcher {
+    records: Vec<BookmarkRecord>,
+    index: SearchIndex,
+    removals: u64,
+    query: Option<SearchQuery>,
+    /// Embedded semantic query of that generation
+    vector: Option<(u64, Vec<f32>)>,
+    embedder: Option<Arc<dyn Embedder>>,
+}
+
+impl Searcher {
+    /// Applies the request, `true` when it should be answered without waiting for more
+    fn apply(&mut self, request: SearchRequest) -> bool {
+        match request {
+            SearchRequest::Push(record, tokens) => {
+                self.index.push(match tokens {
+                    Some(tokens) => *tokens,
+                    None => RecordTokens::new(&record),
+                });
+                self.records.push(*record);
+                false
+            }
+            SearchRequest::Update(index, record) => {
+                self.index.update(index, RecordTokens::new(&record));
+                self.records[index] = *record;
+                false
+            }
+            SearchRequest::Remove(index) => {
+                self.index.remove(index);
+                self.records.remove(index);
+                self.removals += 1;
+                false
+            }
+            SearchRequest::Query(query) => {
+                self.query = query;
+                true
+            }
+        }
+    }
+
+    /// Results of the active query, `None` when there is none
+    fn run(&mut self) -> Option<SearchResults> {
+        let query = self.query.as_ref()?;
+        let items = match query.mode {
+            SearchMode::Fuzzy => self
+                .index
+                .search(&Query::parse(&query.value), |i| &self.records[i]),
+            SearchMode::Semantic => {
+                if self
+                    .vector
+                    .as_ref()
+                    .is_none_or(|(generation, _)| *generation != query.generation)
+                {
+                    let vector = match self.embedder.as_deref().map(|it| it.embed(&query.value)) {
+                        Some(Ok(vector)) => vector,
+                        Some(Err(err)) => {
+                            tracing::warn!("Embedding the query failed: {err}");
+                            vec![]
+                        }
+                        None => vec![],
+                    };
+                    self.vector = Some((query.generation, vector));
+                }
+                let vector = self.vector.as_ref().map(|it| it.1.as_slice());
+                bookmarks_data::semantic_search(vector.unwrap_or_default(), &self.records)
+                    .into_iter()
+                    .map(|(i, similarity)| {
+                        let score = (similarity * 1000.0).round() as i64;
+                        (
+                            i,
+                            Match {
+                                score,
+                                ..Default::default()
+                            },
+                        )
+                    })
+                    .collect()
+            }
+        };
+        Some(SearchResults {
+            generation: query.generation,
+            removals: self.removals,
+            items,
+        })
+    }
+}
+
+impl SearchWorker {
+    pub fn new(tx: UnboundedSender<SearchResults>) -> Self {
+        Self { tx, searcher: None }
+    }
+
+    /// Searches on a blocking thread with its own copy of the items, fed by the returned sender.
+    /// The active query runs again whenever items change, so results follow loading.
+    pub fn start(&mut self, embedder: Option<Arc<dyn Embedder>>) -> mpsc::Sender<SearchRequest> {
+        let (requests, rx) = mpsc::channel();
+        let tx = self.tx.clone();
+
+        let handle = tokio::task::spawn_blocking(move || {
+            let mut searcher = Searcher {
+                embedder,
+                ..Default::default()
+            };
+            while let Ok(request) = rx.recv() {
+                let mut urgent = searcher.apply(request);
+                let deadline = Instant::now() + BATCH_DELAY;
+                // Queries are answered right away, new items wait a little for more
+                loop {
+                    let request = match urgent {
+                        true => rx.try_recv().ok(),
+                        false => rx
+                            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                            .ok(),
+                    };
+                    match request {
+                        Some(request) => urgent |= searcher.apply(request),
+                        None => break,
+                    }
+                }
+                if let Some(results) = searcher.run() {
+                    tx.send(results)?;
+                }
+            }
+            Ok(())
+        });
+        self.searcher = Some(handle);
+        requests
+    }
+}
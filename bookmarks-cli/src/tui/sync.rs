@@ -0,0 +1,97 @@
+use std::{path::PathBuf, time::Duration};
+
+use bookmarks_data::BookmarkRecord;
+use bookmarks_sync::{GitSync, MergeStrategy, SyncReport, SyncStatus};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+
+const POLL_RATE: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub struct SyncWorker {
+    tx: UnboundedSender<SyncEvent>,
+    poller: Option<JoinHandle<color_eyre::Result<()>>>,
+}
+
+#[derive(Debug)]
+pub enum SyncEvent {
+    Status(SyncStatus),
+    Done(SyncDone),
+}
+
+/// Git work asked for by a view, run off the event loop
+#[derive(Debug, Clone)]
+pub enum SyncRequest {
+    /// Writes the record over the conflicted file, relative to the data directory, and stages it
+    Resolve(PathBuf, Box<BookmarkRecord>),
+    /// Continues the sync stopped on conflicts and pushes
+    Resume,
+}
+
+/// Outcome of a [`SyncRequest`], errors as their message
+#[derive(Debug, Clone)]
+pub enum SyncDone {
+    Resolved(PathBuf, Result<(), String>),
+    Resumed(Result<SyncReport, String>),
+}
+
+impl SyncWorker {
+    pub fn new(tx: UnboundedSender<SyncEvent>) -> Self {
+        Self { tx, poller: None }
+    }
+
+    /// Polls the git status of the data directory, does nothing outside of a git work tree
+    pub fn watch_status(&mut self, data: PathBuf) {
+        let tx = self.tx.clone();
+
+        let handle = tokio::spawn(async move {
+            let git = match tokio::task::spawn_blocking(move || GitSync::open(data)).await? {
+                Ok(git) => git,
+                Err(e) => {
+                    tracing::debug!("Git sync status disabled. {e}");
+                    return Ok(());
+                }
+            };
+            let mut interval = tokio::time::interval(POLL_RATE);
+            loop {
+                interval.tick().await;
+                let git = git.clone();
+                match tokio::task::spawn_blocking(move || git.status()).await? {
+                    Ok(status) => tx.send(SyncEvent::Status(status))?,
+                    Err(e) => tracing::warn!("Failed to read git status. {e}"),
+                }
+            }
+        });
+        self.poller = Some(handle);
+    }
+
+    /// Runs the request in the background, its outcome arrives as [`SyncEvent::Done`]
+    pub fn run(&self, data: PathBuf, request: SyncRequest) {
+        let tx = self.tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let git = GitSync::open(data);
+            let done = match request {
+                SyncRequest::Resolve(path, record) => {
+                    let result = git.and_then(|git| git.resolve(&path, &record));
+                    SyncDone::Resolved(path, result.map_err(|e| e.to_string()))
+                }
+                SyncRequest::Resume => SyncDone::Resumed(
+                    git.and_then(|git| git.resume(true, MergeStrategy::Markers))
+                        .map_err(|e| e.to_string()),
+                ),
+            };
+            if tx.send(SyncEvent::Done(done)).is_err() {
+                tracing::debug!("Sync finished after the interface closed");
+            }
+        });
+    }
+}
+
+impl Drop for SyncWorker {
+    fn drop(&mut self) {
+        if let Some(poller) = &self.poller
+            && !poller.is_finished()
+        {
+            poller.abort();
+        }
+    }
+}
@@ -48,6 +48,16 @@ impl TerminalPoller {
     }
 }
 
+impl TerminalPoller {
+    /// Stops reading the terminal, e.g. while another program owns it
+    pub async fn stop(&mut self) {
+        if let Some(poller) = self.poller.take() {
+            poller.abort();
+            let _ = poller.await;
+        }
+    }
+}
+
 impl Drop for TerminalPoller {
     fn drop(&mut self) {}
 }
@@ -0,0 +1,218 @@
+use std::{collections::HashMap, io};
+
+use crate::{BookmarkFile, BookmarkRecord, delete_from_fs, save_to_fs};
+
+/// Query parameters only tracking where a visitor came from
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "yclid",
+];
+
+fn is_tracking_param(param: &str) -> bool {
+    let name = param.split('=').next().unwrap_or_default();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name)
+}
+
+/// The url as compared for duplicates: without the http(s) scheme, `www.`, default ports,
+/// trailing slashes and tracking parameters like `utm_*`, with a lowercase host.
+///
+/// `https://www.Example.com/a/?utm_source=x#top` becomes `example.com/a#top`.
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let (scheme, rest) = match url.split_once("://") {
+        Some((scheme, rest)) => (scheme.to_ascii_lowercase(), rest),
+        None => (String::new(), url),
+    };
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) if !fragment.is_empty() => (rest, Some(fragment)),
+        Some((rest, _)) => (rest, None),
+        None => (rest, None),
+    };
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let (authority, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+
+    let mut host = authority.to_lowercase();
+    if let Some(stripped) = host.strip_prefix("www.") {
+        host = stripped.to_string();
+    }
+    for port in [":80", ":443"] {
+        if let Some(stripped) = host.strip_suffix(port) {
+            host = stripped.to_string();
+        }
+    }
+
+    let mut normalized = match scheme.as_str() {
+        "http" | "https" | "" => host,
+        scheme => format!("{scheme}://{host}"),
+    };
+    normalized.push_str(path.trim_end_matches('/'));
+    let params: Vec<&str> = query
+        .split('&')
+        .filter(|it| !it.is_empty() && !is_tracking_param(it))
+        .collect();
+    if !params.is_empty() {
+        normalized.push('?');
+        normalized.push_str(&params.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        normalized.push('#');
+        normalized.push_str(fragment);
+    }
+    normalized
+}
+
+/// Records sharing a normalized url
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// The shared url, see [`normalize_url`]
+    pub url: String,
+    /// Indices of the records, in their order
+    pub records: Vec<usize>,
+}
+
+/// Groups the records by [`normalize_url`], only groups of several records are returned,
+/// ordered by their first record.
+pub fn find_duplicates<'a>(
+    records: impl IntoIterator<Item = &'a BookmarkRecord>,
+) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<DuplicateGroup> = vec![];
+    let mut by_url: HashMap<String, usize> = HashMap::new();
+    for (i, record) in records.into_iter().enumerate() {
+        let url = normalize_url(&record.url);
+        match by_url.get(&url) {
+            Some(group) => groups[*group].records.push(i),
+            None => {
+                by_url.insert(url.clone(), groups.len());
+                groups.push(DuplicateGroup {
+                    url,
+                    records: vec![i],
+                });
+            }
+        }
+    }
+    groups.retain(|it| it.records.len() > 1);
+    groups
+}
+
+/// Combines `others` into `kept`: tags are joined in order, different descriptions
+/// are appended, a missing title is taken from the others.
+pub fn merge_duplicate_records<'a>(
+    kept: &BookmarkRecord,
+    others: impl IntoIterator<Item = &'a BookmarkRecord>,
+) -> BookmarkRecord {
+    let mut merged = kept.clone();
+    let mut tags = kept.tags.clone().unwrap_or_default();
+    let mut descriptions: Vec<&str> = kept
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|it| !it.is_empty())
+        .into_iter()
+        .collect();
+    for other in others {
+        if merged
+            .title
+            .as_deref()
+            .is_none_or(|it| it.trim().is_empty())
+        {
+            merged.title = other.title.clone();
+        }
+        for tag in other.tags.iter().flatten() {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        if let Some(description) = other.description.as_deref().map(str::trim)
+            && !description.is_empty()
+            && !descriptions.contains(&description)
+        {
+            descriptions.push(description);
+        }
+    }
+    merged.tags = (!tags.is_empty()).then_some(tags);
+    merged.description = (!descriptions.is_empty()).then(|| descriptions.join("\n\n"));
+    merged
+}
+
+/// Saves the [`merge_duplicate_records`] of the files into `kept` and deletes the others.
+/// Returns the merged file.
+#[tracing::instrument(skip_all, fields(path = ?kept.path))]
+pub fn merge_duplicates(kept: &BookmarkFile, others: &[BookmarkFile]) -> io::Result<BookmarkFile> {
+    let merged = BookmarkFile {
+        content: merge_duplicate_records(&kept.content, others.iter().map(|it| &it.content)),
+        ..kept.clone()
+    };
+    save_to_fs(&merged)?;
+    for other in others.iter().filter(|it| it.path != kept.path) {
+        delete_from_fs(other)?;
+    }
+    tracing::debug!("Merged {} duplicate(s)", others.len());
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(title: &str, tags: &[&str]) -> BookmarkRecord {
+        BookmarkRecord {
+            title: Some(title.to_string()),
+            url: format!("https://example.com/{}", title.replace(' ', "-")),
+            tags: Some(tags.iter().map(|it| it.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merges_titles_tags_and_descriptions() {
+        let mut kept = record("", &[]);
+        kept.description = Some("Same ".to_string());
+        let mut first = record("First", &["a", "b"]);
+        first.description = Some("Same".to_string());
+        let mut second = record("Second", &["b", "c"]);
+        second.description = Some("Other".to_string());
+
+        let merged = merge_duplicate_records(&kept, [&first, &second]);
+        assert_eq!(merged.url, kept.url);
+        assert_eq!(merged.title.as_deref(), Some("First"));
+        assert_eq!(merged.tags.as_deref().unwrap(), ["a", "b", "c"]);
+        assert_eq!(merged.description.as_deref(), Some("Same\n\nOther"));
+
+        let merged = merge_duplicate_records(&record("Kept", &[]), []);
+        assert_eq!(merged.title.as_deref(), Some("Kept"));
+        assert_eq!(merged.tags, None);
+        assert_eq!(merged.description, None);
+    }
+
+    #[test]
+    fn find_and_merge_duplicates() {
+        assert_eq!(
+            normalize_url("https://www.Example.com/a/?utm_source=x&id=2&fbclid=y#top"),
+            "example.com/a?id=2#top"
+        );
+        assert_eq!(normalize_url("http://example.com:80/"), "example.com");
+        assert_ne!(normalize_url("ftp://example.com"), "example.com");
+
+        let root = tempfile::tempdir().unwrap();
+        let mut a = record("A", &["rust"]);
+        a.url = "https://example.com/a".to_string();
+        let mut b = record("B", &["web", "rust"]);
+        b.url = "http://www.example.com/a/?utm_medium=feed".to_string();
+        b.description = Some("From the feed".to_string());
+        let c = record("C", &[]);
+        let files: Vec<_> = [a, b, c]
+            .into_iter()
+            .map(|it| crate::create_in_fs(root.path(), it).unwrap())
+            .collect();
+
+        let groups = find_duplicates(files.iter().map(|it| &it.content));
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].records, [0, 1]);
+
+        let merged = merge_duplicates(&files[0], &files[1..2]).unwrap();
+        assert!(!files[1].path.exists());
+        let loaded = crate::load_file(root.path(), &merged.relative_path).unwrap();
+        assert_eq!(loaded.content.title.as_deref(), Some("A"));
+        assert_eq!(loaded.content.tags.as_deref().unwrap(), ["rust", "web"]);
+        assert_eq!(loaded.content.description.as_deref(), Some("From the feed"));
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The directory tree could not be walked
+    Walk,
+    /// The file could not be read
+    Read,
+    /// The file is not a valid bookmark TOML document
+    Parse,
+    /// The file parsed but holds an unusable record, e.g. an empty url
+    Invalid,
+}
+
+/// A problem with a single bookmark file found while loading
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub kind: DiagnosticKind,
+    pub message: String,
+    /// 1-based line and column, when known
+    pub location: Option<(usize, usize)>,
+}
+
+/// 1-based line and column of a byte offset
+fn line_col(content: &str, offset: usize) -> (usize, usize) {
+    let prefix = &content[..offset.min(content.len())];
+    let line = prefix.matches('\n').count() + 1;
+    let column = prefix
+        .rsplit('\n')
+        .next()
+        .map(|it| it.chars().count())
+        .unwrap_or_default()
+        + 1;
+    (line, column)
+}
+
+impl Diagnostic {
+    pub fn new(path: impl Into<PathBuf>, kind: DiagnosticKind, err: &io::Error) -> Self {
+        Self {
+            path: path.into(),
+            kind,
+            message: err.to_string(),
+            location: None,
+        }
+    }
+
+    pub fn parse(path: &Path, content: &str, err: &toml::de::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            kind: DiagnosticKind::Parse,
+            message: err.message().trim().to_string(),
+            location: err.span().map(|span| line_col(content, span.start)),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.to_string_lossy())?;
+        if let Some((line, column)) = self.location {
+            write!(f, ":{line}:{column}")?;
+        }
+        let kind = match self.kind {
+            DiagnosticKind::Walk => "walk",
+            DiagnosticKind::Read => "read",
+            DiagnosticKind::Parse => "parse",
+            DiagnosticKind::Invalid => "invalid",
+        };
+        write!(f, ": {kind}: {}", self.message)
+    }
+}
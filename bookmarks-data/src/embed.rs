@@ -0,0 +1,239 @@
+use std::{
+    fmt, io,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use crate::{BookmarkRecord, Query};
+
+/// Turns text into a vector. Records and queries must use the same embedder.
+pub trait Embedder: fmt::Debug + Send + Sync {
+    fn embed(&self, text: &str) -> io::Result<Vec<f32>>;
+}
+
+/// Deterministic bag of words embedder: every word and character trigram is
+/// hashed into one of `dimensions` buckets. No model needed, good enough to
+/// find records sharing vocabulary and stable across runs, so usable in tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashingEmbedder {
+    pub dimensions: usize,
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self { dimensions: 256 }
+    }
+}
+
+/// FNV-1a, stable unlike the std hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl Embedder for HashingEmbedder {
+    fn embed(&self, text: &str) -> io::Result<Vec<f32>> {
+        if self.dimensions == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Embeddings need at least one dimension",
+            ));
+        }
+        let mut vector = vec![0.0; self.dimensions];
+        let mut add = |feature: &str, weight: f32| {
+            let hash = fnv1a(feature.as_bytes());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(hash % self.dimensions as u64) as usize] += sign * weight;
+        };
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|it| !it.is_empty())
+            .map(str::to_lowercase)
+        {
+            add(&word, 1.0);
+            let chars: Vec<char> = format!(" {word} ").chars().collect();
+            for trigram in chars.windows(3) {
+                add(&trigram.iter().collect::<String>(), 0.5);
+            }
+        }
+        normalize(&mut vector);
+        Ok(vector)
+    }
+}
+
+/// Runs a local program for every text: the text goes to its stdin and it
+/// prints the vector as a JSON array of numbers.
+#[derive(Debug, Clone)]
+pub struct CommandEmbedder {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl Embedder for CommandEmbedder {
+    fn embed(&self, text: &str) -> io::Result<Vec<f32>> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{:?} exited with {}",
+                self.program, output.status
+            )));
+        }
+        let mut vector: Vec<f32> = serde_json::from_slice(&output.stdout)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        normalize(&mut vector);
+        Ok(vector)
+    }
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|it| it * it).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|it| *it /= norm);
+    }
+}
+
+/// Text of a record that gets embedded
+pub fn embedding_text(record: &BookmarkRecord) -> String {
+    let mut parts: Vec<&str> = vec![];
+    parts.extend(record.title.as_deref());
+    parts.extend(record.tags.iter().flatten().map(String::as_str));
+    parts.extend(record.path.iter().map(String::as_str));
+    parts.extend(record.description.as_deref());
+    parts.push(&record.url);
+    parts.join("\n")
+}
+
+/// `None` when the lengths differ or a vector is zero
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    if a.len() != b.len() {
+        return None;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    let norm = |v: &[f32]| v.iter().map(|it| it * it).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    (norms > 0.0).then(|| dot / norms)
+}
+
+/// Ranks records by cosine similarity to `query`, best first.
+///
+/// Records without embeddings (or with another dimension) are skipped, as are
+/// records pointing away from the query.
+pub fn semantic_search<'a>(
+    query: &[f32],
+    records: impl IntoIterator<Item = &'a BookmarkRecord>,
+) -> Vec<(usize, f32)> {
+    let mut keys: Vec<_> = records
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, record)| {
+            let similarity = cosine_similarity(query, record.embeddings.as_deref()?)?;
+            (similarity > 0.0).then_some((i, similarity))
+        })
+        .collect();
+    keys.sort_by(|a, b| b.1.total_cmp(&a.1));
+    keys
+}
+
+/// Blends [`semantic_search`] with the [`Query`] score, best first.
+///
+/// Query scores are scaled to `0..=1` by the best one, `semantic_weight` is the
+/// share of the similarity: `1.0` ranks by meaning only, `0.0` like [`crate::search`].
+/// A record needs to match the query or to have a positive similarity.
+pub fn hybrid_search(
+    query: &Query,
+    vector: &[f32],
+    records: &[&BookmarkRecord],
+    semantic_weight: f32,
+) -> Vec<(usize, f32)> {
+    let weight = semantic_weight.clamp(0.0, 1.0);
+    let fuzzy: Vec<Option<i64>> = records.iter().map(|it| query.score(it)).collect();
+    let best = fuzzy
+        .iter()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap_or_default()
+        .max(1) as f32;
+    let mut keys: Vec<_> = records
+        .iter()
+        .enumerate()
+        .filter_map(|(i, record)| {
+            let similarity = record
+                .embeddings
+                .as_deref()
+                .and_then(|it| cosine_similarity(vector, it))
+                .filter(|it| *it > 0.0);
+            if fuzzy[i].is_none() && similarity.is_none() {
+                return None;
+            }
+            let fuzzy = fuzzy[i]
+                .map(|it| it.max(0) as f32 / best)
+                .unwrap_or_default();
+            Some((
+                i,
+                weight * similarity.unwrap_or_default() + (1.0 - weight) * fuzzy,
+            ))
+        })
+        .collect();
+    keys.sort_by(|a, b| b.1.total_cmp(&a.1));
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(title: &str, tags: &[&str]) -> BookmarkRecord {
+        BookmarkRecord {
+            title: Some(title.to_string()),
+            url: format!("https://example.com/{}", title.replace(' ', "-")),
+            tags: Some(tags.iter().map(|it| it.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn hashing_embedder_is_deterministic() {
+        let embedder = HashingEmbedder { dimensions: 64 };
+        let a = embedder.embed("Async Rust book").unwrap();
+        assert_eq!(a, embedder.embed("async rust book").unwrap());
+        assert_eq!(a.len(), 64);
+        let norm: f32 = a.iter().map(|it| it * it).sum();
+        assert!((norm - 1.0).abs() < 1e-5);
+        assert!(HashingEmbedder { dimensions: 0 }.embed("x").is_err());
+    }
+
+    #[test]
+    fn semantic_and_hybrid_search() {
+        let embedder = HashingEmbedder::default();
+        let mut records = vec![
+            record("Gardening tips", &["plants"]),
+            record("Async programming in Rust", &["rust", "async"]),
+            record("Not embedded rust", &["rust"]),
+        ];
+        for record in &mut records[..2] {
+            record.embeddings = Some(embedder.embed(&embedding_text(record)).unwrap());
+        }
+
+        let vector = embedder.embed("rust async").unwrap();
+        let ranked = semantic_search(&vector, &records);
+        assert_eq!(ranked.first().map(|it| it.0), Some(1));
+        assert!(ranked.iter().all(|it| it.0 != 2));
+
+        let refs: Vec<_> = records.iter().collect();
+        let ranked = hybrid_search(&Query::parse("tag:rust"), &vector, &refs, 0.5);
+        assert_eq!(ranked[0].0, 1);
+        assert!(ranked.iter().any(|it| it.0 == 2));
+    }
+}
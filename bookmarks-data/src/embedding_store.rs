@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{BookmarkFile, BookmarkRecord, embedding_text};
+
+/// Sidecar file inside the data directory. Its presence keeps embeddings out of the TOML files.
+pub const EMBEDDINGS_FILE: &str = ".bookmarks-embeddings";
+const MAGIC: &[u8; 8] = b"BMKEMB01";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    /// blake3 of the embedded text, a stale vector is never handed out
+    hash: [u8; 32],
+    vector: Vec<f32>,
+}
+
+/// Embeddings of every bookmark in one binary file, keyed by relative path.
+///
+/// Layout: magic, entry count, then per entry the path length and UTF-8 path,
+/// the content hash, the dimensions and the vector. Integers are `u32` and
+/// floats `f32`, all little endian.
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddingStore {
+    path: PathBuf,
+    entries: HashMap<PathBuf, Entry>,
+}
+
+/// Hash of the [`embedding_text`] without the folder, so moved files keep their vector
+fn content_hash(record: &BookmarkRecord) -> [u8; 32] {
+    let record = BookmarkRecord {
+        path: vec![],
+        embeddings: None,
+        ..record.clone()
+    };
+    *blake3::hash(embedding_text(&record).as_bytes()).as_bytes()
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn write_u32(writer: &mut impl Write, value: usize) -> io::Result<()> {
+    let value = u32::try_from(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    writer.write_all(&value.to_le_bytes())
+}
+
+impl EmbeddingStore {
+    /// Store of the data directory `root`, `None` when it has no sidecar file.
+    pub fn open(root: &Path) -> io::Result<Option<Self>> {
+        let path = root.join(EMBEDDINGS_FILE);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let invalid =
+            |e: io::Error| io::Error::new(io::ErrorKind::InvalidData, format!("{path:?}: {e}"));
+        let mut entries = HashMap::new();
+        let mut reader = data.as_slice();
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic).map_err(invalid)?;
+        if &magic != MAGIC {
+            return Err(invalid(io::Error::other("Unknown format")));
+        }
+        for _ in 0..read_u32(&mut reader).map_err(invalid)? {
+            let mut read_entry = || -> io::Result<(PathBuf, Entry)> {
+                let len = read_u32(&mut reader)? as usize;
+                if len > reader.len() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                let mut relative_path = vec![0; len];
+                reader.read_exact(&mut relative_path)?;
+                let relative_path = String::from_utf8(relative_path)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                let mut hash = [0; 32];
+                reader.read_exact(&mut hash)?;
+                let dimensions = read_u32(&mut reader)? as usize;
+                if dimensions > reader.len() / 4 {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                let mut vector = Vec::with_capacity(dimensions);
+                for _ in 0..dimensions {
+                    vector.push(f32::from_bits(read_u32(&mut reader)?));
+                }
+                Ok((relative_path.into(), Entry { hash, vector }))
+            };
+            let (relative_path, entry) = read_entry().map_err(invalid)?;
+            entries.insert(relative_path, entry);
+        }
+        Ok(Some(Self { path, entries }))
+    }
+
+    /// Like [`EmbeddingStore::open`], starting an empty store when there is none yet.
+    pub fn open_or_create(root: &Path) -> io::Result<Self> {
+        Ok(Self::open(root)?.unwrap_or_else(|| Self {
+            path: root.join(EMBEDDINGS_FILE),
+            entries: HashMap::new(),
+        }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The vector stored for `file`, unless its title, url, tags or description changed since.
+    pub fn get(&self, file: &BookmarkFile) -> Option<&[f32]> {
+        self.entries
+            .get(&file.relative_path)
+            .filter(|entry| entry.hash == content_hash(&file.content))
+            .map(|entry| entry.vector.as_slice())
+    }
+
+    /// Whether `vector` is what the store holds for `path`, current or stale
+    pub fn holds(&self, path: &Path, vector: &[f32]) -> bool {
+        self.entries
+            .get(path)
+            .is_some_and(|entry| entry.vector == vector)
+    }
+
+    pub fn insert(&mut self, file: &BookmarkFile, vector: Vec<f32>) {
+        let hash = content_hash(&file.content);
+        self.entries
+            .insert(file.relative_path.clone(), Entry { hash, vector });
+    }
+
+    /// Follows a moved or renamed file
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        if let Some(entry) = self.entries.remove(from) {
+            self.entries.insert(to.to_path_buf(), entry);
+        }
+    }
+
+    /// Drops the entries of files that no longer exist
+    pub fn retain(&mut self, mut keep: impl FnMut(&Path) -> bool) {
+        self.entries.retain(|path, _| keep(path));
+    }
+
+    /// Fills `embeddings` of the file from the store, when the file has none of its own
+    pub fn attach(&self, file: &mut BookmarkFile) {
+        if file.content.embeddings.is_none() {
+            file.content.embeddings = self.get(file).map(<[f32]>::to_vec);
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut data = MAGIC.to_vec();
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|(path, entry)| Some((path.to_str()?, entry)))
+            .collect();
+        entries.sort_by_key(|it| it.0);
+        write_u32(&mut data, entries.len())?;
+        for (path, entry) in entries {
+            write_u32(&mut data, path.len())?;
+            data.extend_from_slice(path.as_bytes());
+            data.extend_from_slice(&entry.hash);
+            write_u32(&mut data, entry.vector.len())?;
+            for value in &entry.vector {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        tmp.write_all(&data)?;
+        tmp.persist(&self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Embedder, HashingEmbedder, create_in_fs, delete_from_fs, load_file, move_in_fs,
+        rename_in_fs, save_to_fs,
+    };
+
+    fn record(title: &str, tags: &[&str]) -> BookmarkRecord {
+        BookmarkRecord {
+            title: Some(title.to_string()),
+            url: format!("https://example.com/{}", title.replace(' ', "-")),
+            tags: Some(tags.iter().map(|it| it.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn embedding_store_round_trip_and_invalidation() {
+        let root = tempfile::tempdir().unwrap();
+        let embedder = HashingEmbedder { dimensions: 8 };
+        let mut file = create_in_fs(root.path(), record("Rust book", &["rust"])).unwrap();
+        let vector = embedder.embed(&embedding_text(&file.content)).unwrap();
+
+        let mut store = EmbeddingStore::open_or_create(root.path()).unwrap();
+        store.insert(&file, vector.clone());
+        store.save().unwrap();
+
+        let loaded = load_file(root.path(), &file.relative_path).unwrap();
+        assert_eq!(
+            loaded.content.embeddings.as_deref(),
+            Some(vector.as_slice())
+        );
+        // Kept out of the TOML file
+        save_to_fs(&loaded).unwrap();
+        assert!(
+            !fs::read_to_string(&file.path)
+                .unwrap()
+                .contains("embeddings")
+        );
+
+        rename_in_fs(&mut file, "renamed").unwrap();
+        let loaded = load_file(root.path(), &file.relative_path).unwrap();
+        assert!(loaded.content.embeddings.is_some());
+        move_in_fs(&mut file, &["books".to_string()]).unwrap();
+        let loaded = load_file(root.path(), &file.relative_path).unwrap();
+        assert_eq!(
+            loaded.content.embeddings.as_deref(),
+            Some(vector.as_slice())
+        );
+
+        file.content.title = Some("Gardening".to_string());
+        save_to_fs(&file).unwrap();
+        let loaded = load_file(root.path(), &file.relative_path).unwrap();
+        assert!(loaded.content.embeddings.is_none());
+
+        delete_from_fs(&file).unwrap();
+        assert!(
+            EmbeddingStore::open(root.path())
+                .unwrap()
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+use strum::{Display, EnumString};
+
+use crate::{BookmarkRecord, Query};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ExportFormat {
+    /// Netscape bookmark file, importable by browsers
+    #[default]
+    Html,
+    /// One JSON object per line
+    Jsonl,
+    Csv,
+}
+
+/// Selects the records to export, every set criterion has to match
+#[derive(Debug, Default)]
+pub struct ExportFilter {
+    pub query: Option<Query>,
+    pub tags: Vec<String>,
+    /// Folder prefix, `[]` for everything
+    pub folder: Vec<String>,
+}
+
+impl ExportFilter {
+    pub fn matches(&self, record: &BookmarkRecord) -> bool {
+        let tags = record.tags.as_deref().unwrap_or_default();
+        record.path.starts_with(&self.folder)
+            && self.tags.iter().all(|tag| tags.contains(tag))
+            && self
+                .query
+                .as_ref()
+                .is_none_or(|query| query.matches(record))
+    }
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    folder: &'a [String],
+    title: Option<&'a str>,
+    url: &'a str,
+    tags: &'a [String],
+    description: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct CsvRecord<'a> {
+    folder: String,
+    title: &'a str,
+    url: &'a str,
+    tags: String,
+    description: &'a str,
+}
+
+/// Writes `records` to `out`, returns the number of exported records.
+///
+/// Json lines and csv are streamed, html collects the records to nest them by folder.
+#[tracing::instrument(skip(records, out))]
+pub fn export<W: Write>(
+    format: ExportFormat,
+    records: impl IntoIterator<Item = BookmarkRecord>,
+    mut out: W,
+) -> io::Result<usize> {
+    let mut count = 0;
+    match format {
+        ExportFormat::Html => {
+            let mut records: Vec<_> = records.into_iter().collect();
+            records.sort_by(|a, b| a.path.cmp(&b.path));
+            count = records.len();
+            write_html(&records, &mut out)?;
+        }
+        ExportFormat::Jsonl => {
+            for record in records {
+                serde_json::to_writer(
+                    &mut out,
+                    &JsonRecord {
+                        folder: &record.path,
+                        title: record.title.as_deref(),
+                        url: &record.url,
+                        tags: record.tags.as_deref().unwrap_or_default(),
+                        description: record.description.as_deref(),
+                    },
+                )?;
+                out.write_all(b"\n")?;
+                count += 1;
+            }
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(&mut out);
+            for record in records {
+                writer
+                    .serialize(CsvRecord {
+                        folder: record.path.join("/"),
+                        title: record.title.as_deref().unwrap_or_default(),
+                        url: &record.url,
+                        tags: record.tags.as_deref().unwrap_or_default().join(","),
+                        description: record.description.as_deref().unwrap_or_default(),
+                    })
+                    .map_err(io::Error::other)?;
+                count += 1;
+            }
+            writer.flush()?;
+        }
+    }
+    out.flush()?;
+    Ok(count)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `records` must be sorted by folder
+fn write_html<W: Write>(records: &[BookmarkRecord], out: &mut W) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE NETSCAPE-Bookmark-file-1>")?;
+    writeln!(
+        out,
+        r#"<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">"#
+    )?;
+    writeln!(out, "<TITLE>Bookmarks</TITLE>")?;
+    writeln!(out, "<H1>Bookmarks</H1>")?;
+    writeln!(out, "<DL><p>")?;
+
+    let mut open: &[String] = &[];
+    for record in records {
+        let common = open
+            .iter()
+            .zip(&record.path)
+            .take_while(|(a, b)| a == b)
+            .count();
+        for depth in (common..open.len()).rev() {
+            writeln!(out, "{}</DL><p>", indent(depth + 1))?;
+        }
+        for (depth, folder) in record.path.iter().enumerate().skip(common) {
+            writeln!(out, "{}<DT><H3>{}</H3>", indent(depth + 1), escape(folder))?;
+            writeln!(out, "{}<DL><p>", indent(depth + 1))?;
+        }
+        open = &record.path;
+
+        let depth = open.len() + 1;
+        write!(
+            out,
+            r#"{}<DT><A HREF="{}""#,
+            indent(depth),
+            escape(&record.url)
+        )?;
+        if let Some(tags) = record.tags.as_deref().filter(|it| !it.is_empty()) {
+            write!(out, r#" TAGS="{}""#, escape(&tags.join(",")))?;
+        }
+        let title = record.title.as_deref().unwrap_or(&record.url);
+        writeln!(out, ">{}</A>", escape(title))?;
+        if let Some(description) = record.description.as_deref() {
+            writeln!(out, "{}<DD>{}", indent(depth), escape(description))?;
+        }
+    }
+    for depth in (0..open.len()).rev() {
+        writeln!(out, "{}</DL><p>", indent(depth + 1))?;
+    }
+    writeln!(out, "</DL><p>")
+}
+
+fn indent(depth: usize) -> String {
+    "    ".repeat(depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_netscape;
+
+    fn records() -> Vec<BookmarkRecord> {
+        let record =
+            |path: &[&str], title: Option<&str>, url: &str, tags: &[&str]| BookmarkRecord {
+                path: path.iter().map(|it| it.to_string()).collect(),
+                title: title.map(str::to_string),
+                url: url.to_string(),
+                tags: (!tags.is_empty()).then(|| tags.iter().map(|it| it.to_string()).collect()),
+                ..Default::default()
+            };
+        let mut described = record(
+            &["dev", "rust"],
+            Some("Tom & \"Jerry\" <3"),
+            "https://a.example/?x=1&y=2",
+            &["rust", "web"],
+        );
+        described.description = Some("Says <hi>, \"twice\"".to_string());
+        vec![
+            record(&["dev"], Some("Dev"), "https://dev.example", &[]),
+            described,
+            record(&["news"], None, "https://news.example", &["daily"]),
+            record(&[], Some("Root"), "https://root.example", &[]),
+        ]
+    }
+
+    fn exported(format: ExportFormat) -> String {
+        let mut out = vec![];
+        let count = export(format, records(), &mut out).unwrap();
+        assert_eq!(count, 4);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn html_round_trips_through_the_importer() {
+        let mut expected = records();
+        expected.sort_by(|a, b| a.path.cmp(&b.path));
+        // The importer takes the url for a missing title
+        expected[3].title = Some("https://news.example".to_string());
+
+        let parsed = parse_netscape(&exported(ExportFormat::Html));
+        let fields = |it: &BookmarkRecord| {
+            (
+                it.path.clone(),
+                it.title.clone(),
+                it.url.clone(),
+                it.tags.clone(),
+                it.description.clone(),
+            )
+        };
+        assert_eq!(
+            parsed.iter().map(fields).collect::<Vec<_>>(),
+            expected.iter().map(fields).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn jsonl_and_csv_hold_every_field() {
+        let jsonl = exported(ExportFormat::Jsonl);
+        let lines: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|it| serde_json::from_str(it).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1]["folder"], serde_json::json!(["dev", "rust"]));
+        assert_eq!(lines[1]["tags"], serde_json::json!(["rust", "web"]));
+        assert_eq!(lines[1]["description"], "Says <hi>, \"twice\"");
+        assert_eq!(lines[2]["title"], serde_json::Value::Null);
+
+        let csv = exported(ExportFormat::Csv);
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["folder", "title", "url", "tags", "description"]
+        );
+        let rows: Vec<_> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[1].iter().collect::<Vec<_>>(),
+            [
+                "dev/rust",
+                "Tom & \"Jerry\" <3",
+                "https://a.example/?x=1&y=2",
+                "rust,web",
+                "Says <hi>, \"twice\""
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_needs_every_criterion() {
+        let records = records();
+        let matching = |filter: ExportFilter| {
+            records
+                .iter()
+                .filter(|it| filter.matches(it))
+                .map(|it| it.url.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matching(ExportFilter::default()).len(), 4);
+        assert_eq!(
+            matching(ExportFilter {
+                folder: vec!["dev".to_string()],
+                ..Default::default()
+            }),
+            ["https://dev.example", "https://a.example/?x=1&y=2"]
+        );
+        assert_eq!(
+            matching(ExportFilter {
+                folder: vec!["dev".to_string()],
+                tags: vec!["web".to_string()],
+                ..Default::default()
+            }),
+            ["https://a.example/?x=1&y=2"]
+        );
+        assert_eq!(
+            matching(ExportFilter {
+                query: Some(Query::parse("host:news.example")),
+                ..Default::default()
+            }),
+            ["https://news.example"]
+        );
+        assert!(
+            matching(ExportFilter {
+                query: Some(Query::parse("host:news.example")),
+                tags: vec!["rust".to_string()],
+                ..Default::default()
+            })
+            .is_empty()
+        );
+    }
+}
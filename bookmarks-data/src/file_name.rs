@@ -0,0 +1,94 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::BookmarkRecord;
+
+const MAX_STEM_LEN: usize = 64;
+
+/// Lowercase file stem built from the title (or the url when there is no title).
+pub fn stem_for(record: &BookmarkRecord) -> String {
+    let source = match record.title.as_deref().map(str::trim) {
+        Some(title) if !title.is_empty() => title,
+        _ => record
+            .url
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .unwrap_or(&record.url),
+    };
+    slugify(source)
+}
+
+pub fn slugify(source: &str) -> String {
+    let mut slug = String::new();
+    for ch in source.chars().flat_map(char::to_lowercase) {
+        if ch.is_alphanumeric() {
+            slug.push(ch);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.chars().count() >= MAX_STEM_LEN {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "bookmark".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Validates a single folder (or file stem) name so it cannot escape the data root.
+pub fn check_component(name: &str) -> io::Result<&str> {
+    let trimmed = name.trim();
+    if trimmed.is_empty()
+        || trimmed == "."
+        || trimmed == ".."
+        || trimmed.contains(['/', '\\', '\0'])
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid name {name:?}"),
+        ));
+    }
+    Ok(trimmed)
+}
+
+/// Validates a file stem: a checked component that is not hidden, the loader skips hidden files.
+pub fn check_stem(stem: &str) -> io::Result<&str> {
+    let stem = check_component(stem)?;
+    if stem.starts_with('.') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid name {stem:?}"),
+        ));
+    }
+    Ok(stem)
+}
+
+/// Trimmed copy of `folder` with every component checked.
+pub fn check_folder(folder: &[String]) -> io::Result<Vec<String>> {
+    folder
+        .iter()
+        .map(|part| check_component(part).map(str::to_string))
+        .collect()
+}
+
+/// Directory for `folder` inside `root`, with every component checked.
+pub fn folder_path(root: &Path, folder: &[String]) -> io::Result<PathBuf> {
+    let mut dir = root.to_path_buf();
+    for part in folder {
+        dir.push(check_component(part)?);
+    }
+    Ok(dir)
+}
+
+/// `stem.toml`, then `stem-2.toml`, `stem-3.toml`... for retries after a collision.
+pub fn candidate(stem: &str, attempt: usize) -> String {
+    match attempt {
+        0 | 1 => format!("{stem}.toml"),
+        n => format!("{stem}-{n}.toml"),
+    }
+}
@@ -0,0 +1,117 @@
+use std::io;
+
+use serde::Deserialize;
+
+use crate::BookmarkRecord;
+
+use super::folder_name;
+
+#[derive(Deserialize)]
+struct BookmarksFile {
+    roots: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Node {
+    Url {
+        name: String,
+        url: String,
+    },
+    Folder {
+        name: String,
+        #[serde(default)]
+        children: Vec<Node>,
+    },
+}
+
+fn collect(node: Node, folder: &mut Vec<String>, records: &mut Vec<BookmarkRecord>) {
+    match node {
+        Node::Url { name, url } => {
+            let name = name.trim();
+            records.push(BookmarkRecord {
+                path: folder.clone(),
+                title: (!name.is_empty()).then(|| name.to_string()),
+                url,
+                ..Default::default()
+            });
+        }
+        Node::Folder { name, children } => {
+            folder.push(folder_name(&name));
+            for child in children {
+                collect(child, folder, records);
+            }
+            folder.pop();
+        }
+    }
+}
+
+/// Parses the `Bookmarks` JSON file of a Chromium profile.
+///
+/// Every root ("Bookmarks bar", "Other bookmarks"...) becomes a top level folder.
+pub fn parse(json: &str) -> io::Result<Vec<BookmarkRecord>> {
+    let file: BookmarksFile =
+        serde_json::from_str(json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut records = vec![];
+    // `roots` also holds non folder entries, like `sync_transaction_version`
+    for root in file.roots.into_values() {
+        if let Ok(node @ Node::Folder { .. }) = serde_json::from_value(root) {
+            collect(node, &mut vec![], &mut records);
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOKMARKS: &str = r#"{
+        "checksum": "0",
+        "roots": {
+            "bookmark_bar": {
+                "type": "folder",
+                "name": "Bookmarks bar",
+                "children": [
+                    { "type": "url", "name": " Rust ", "url": "https://rust-lang.org" },
+                    {
+                        "type": "folder",
+                        "name": "Dev/Tools",
+                        "children": [
+                            { "type": "url", "name": "", "url": "https://docs.rs", "date_added": "1" },
+                            { "type": "folder", "name": "Empty" }
+                        ]
+                    }
+                ]
+            },
+            "other": { "type": "folder", "name": "Other bookmarks", "children": [] },
+            "sync_transaction_version": "1"
+        },
+        "version": 1
+    }"#;
+
+    #[test]
+    fn reads_every_root() {
+        let records = parse(BOOKMARKS).unwrap();
+        let found: Vec<_> = records
+            .iter()
+            .map(|it| (it.path.join("/"), it.title.as_deref(), it.url.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "Bookmarks bar".to_string(),
+                    Some("Rust"),
+                    "https://rust-lang.org"
+                ),
+                (
+                    "Bookmarks bar/Dev-Tools".to_string(),
+                    None,
+                    "https://docs.rs"
+                ),
+            ]
+        );
+        assert_eq!(parse("{}").unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,248 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use rusqlite::{Connection, OpenFlags};
+
+use crate::BookmarkRecord;
+
+use super::folder_name;
+
+const TYPE_BOOKMARK: i64 = 1;
+const TAGS_ROOT: &str = "tags________";
+
+struct Item {
+    kind: i64,
+    parent: i64,
+    title: Option<String>,
+    guid: String,
+    place: Option<i64>,
+    url: Option<String>,
+}
+
+/// Display name of the built in roots, their titles are empty in recent profiles
+fn root_name(guid: &str) -> Option<&'static str> {
+    match guid {
+        "menu________" => Some("Bookmarks Menu"),
+        "toolbar_____" => Some("Bookmarks Toolbar"),
+        "unfiled_____" => Some("Other Bookmarks"),
+        "mobile______" => Some("Mobile Bookmarks"),
+        _ => None,
+    }
+}
+
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Reads the bookmarks of a Firefox profile `places.sqlite`.
+///
+/// Firefox keeps the database locked while running, so it is copied (with its
+/// write ahead log and the log's shared memory index, without which a read only
+/// open fails) to a temporary directory and opened read only.
+/// Tags and keywords both become `tags`.
+pub fn read(path: &Path) -> io::Result<Vec<BookmarkRecord>> {
+    let dir = tempfile::tempdir()?;
+    let copy = dir.path().join("places.sqlite");
+    fs::copy(path, &copy)?;
+    for suffix in ["-wal", "-shm"] {
+        let mut side = path.as_os_str().to_owned();
+        side.push(suffix);
+        if Path::new(&side).is_file() {
+            fs::copy(&side, dir.path().join(format!("places.sqlite{suffix}")))?;
+        }
+    }
+    let connection =
+        Connection::open_with_flags(&copy, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(sql_error)?;
+    query(&connection).map_err(sql_error)
+}
+
+fn query(connection: &Connection) -> rusqlite::Result<Vec<BookmarkRecord>> {
+    let mut statement = connection.prepare(
+        "SELECT b.id, b.type, b.parent, b.title, b.guid, b.fk, p.url
+         FROM moz_bookmarks b LEFT JOIN moz_places p ON p.id = b.fk
+         ORDER BY b.parent, b.position",
+    )?;
+    let mut order = vec![];
+    let mut items = HashMap::new();
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            Item {
+                kind: row.get(1)?,
+                parent: row.get(2)?,
+                title: row.get(3)?,
+                guid: row.get(4)?,
+                place: row.get(5)?,
+                url: row.get(6)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (id, item) = row?;
+        order.push(id);
+        items.insert(id, item);
+    }
+
+    let mut keywords: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut statement = connection.prepare("SELECT place_id, keyword FROM moz_keywords")?;
+    for row in statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+        let (place, keyword) = row?;
+        keywords.entry(place).or_default().push(keyword);
+    }
+
+    // Ancestor chain, the root comes last
+    let ancestors = |mut id: i64| {
+        let mut chain = vec![];
+        while let Some(item) = items.get(&id) {
+            chain.push(item);
+            id = item.parent;
+        }
+        chain
+    };
+    let in_tags = |chain: &[&Item]| chain.iter().any(|it| it.guid == TAGS_ROOT);
+
+    // Tags are folders below the tags root, holding one bookmark per tagged place
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    for item in items.values().filter(|it| it.kind == TYPE_BOOKMARK) {
+        if let Some(parent) = items.get(&item.parent)
+            && items
+                .get(&parent.parent)
+                .is_some_and(|it| it.guid == TAGS_ROOT)
+            && let (Some(place), Some(tag)) = (item.place, parent.title.as_deref())
+        {
+            tags.entry(place).or_default().push(tag.to_string());
+        }
+    }
+
+    let mut records = vec![];
+    for id in order {
+        let item = &items[&id];
+        let Some(url) = item.url.as_deref() else {
+            continue;
+        };
+        let chain = ancestors(item.parent);
+        if item.kind != TYPE_BOOKMARK || in_tags(&chain) || url.starts_with("place:") {
+            continue;
+        }
+        let path = chain
+            .iter()
+            .rev()
+            // The unnamed root of every root
+            .skip(1)
+            .map(|it| match root_name(&it.guid) {
+                Some(name) => name.to_string(),
+                None => folder_name(it.title.as_deref().unwrap_or_default()),
+            })
+            .collect();
+        let mut record_tags: Vec<String> = vec![];
+        for tag in item.place.into_iter().flat_map(|place| {
+            let tags = tags.get(&place).into_iter().flatten();
+            tags.chain(keywords.get(&place).into_iter().flatten())
+        }) {
+            if !record_tags.contains(tag) {
+                record_tags.push(tag.clone());
+            }
+        }
+        let title = item.title.as_deref().map(str::trim).unwrap_or_default();
+        records.push(BookmarkRecord {
+            path,
+            title: (!title.is_empty()).then(|| title.to_string()),
+            url: url.to_string(),
+            tags: (!record_tags.is_empty()).then_some(record_tags),
+            ..Default::default()
+        });
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TYPE_FOLDER: i64 = 2;
+
+    /// A profile database in WAL mode, returned with its still open connection
+    /// so the last writes stay in the write ahead log
+    fn places(dir: &Path) -> Connection {
+        let connection = Connection::open(dir.join("places.sqlite")).unwrap();
+        connection
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 PRAGMA wal_autocheckpoint = 0;
+                 CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT);
+                 CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER,
+                     parent INTEGER, position INTEGER, title TEXT, guid TEXT);
+                 CREATE TABLE moz_keywords (id INTEGER PRIMARY KEY, keyword TEXT, place_id INTEGER);",
+            )
+            .unwrap();
+        let places = [
+            (1, "https://rust-lang.org"),
+            (2, "place:sort=8"),
+            (3, "https://news.example"),
+        ];
+        for (id, url) in places {
+            connection
+                .execute("INSERT INTO moz_places VALUES (?1, ?2)", (id, url))
+                .unwrap();
+        }
+        let bookmarks = [
+            (1, TYPE_FOLDER, None::<i64>, 0, 0, "", "root________"),
+            (2, TYPE_FOLDER, None, 1, 0, "", "menu________"),
+            (3, TYPE_FOLDER, None, 1, 1, "", "toolbar_____"),
+            (4, TYPE_FOLDER, None, 1, 2, "", TAGS_ROOT),
+            (5, TYPE_FOLDER, None, 3, 0, "Dev/Tools", "folder______"),
+            (6, TYPE_BOOKMARK, Some(1), 5, 0, " Rust ", "bookmark1___"),
+            (7, TYPE_FOLDER, None, 4, 0, "lang", "tag_________"),
+            (8, TYPE_BOOKMARK, Some(1), 7, 0, "", "tagged______"),
+            (9, TYPE_BOOKMARK, Some(2), 2, 0, "Recent", "query_______"),
+        ];
+        for (id, kind, fk, parent, position, title, guid) in bookmarks {
+            connection
+                .execute(
+                    "INSERT INTO moz_bookmarks VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    (id, kind, fk, parent, position, title, guid),
+                )
+                .unwrap();
+        }
+        connection
+            .execute("INSERT INTO moz_keywords VALUES (1, 'rs', 1)", ())
+            .unwrap();
+        connection
+    }
+
+    #[test]
+    fn reads_a_live_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let connection = places(dir.path());
+        connection
+            .execute(
+                "INSERT INTO moz_bookmarks VALUES (10, ?1, 3, 2, 1, 'News', 'bookmark2___')",
+                [TYPE_BOOKMARK],
+            )
+            .unwrap();
+        assert!(dir.path().join("places.sqlite-wal").is_file());
+        assert!(dir.path().join("places.sqlite-shm").is_file());
+
+        let records = read(&dir.path().join("places.sqlite")).unwrap();
+        let found: Vec<_> = records
+            .iter()
+            .map(|it| (it.path.join("/"), it.title.as_deref(), it.url.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "Bookmarks Menu".to_string(),
+                    Some("News"),
+                    "https://news.example"
+                ),
+                (
+                    "Bookmarks Toolbar/Dev-Tools".to_string(),
+                    Some("Rust"),
+                    "https://rust-lang.org"
+                ),
+            ]
+        );
+        assert_eq!(records[1].tags.as_deref().unwrap(), ["lang", "rs"]);
+        drop(connection);
+    }
+}
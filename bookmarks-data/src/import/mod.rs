@@ -0,0 +1,159 @@
+use std::{collections::HashSet, fmt, fs, io, path::Path};
+
+use strum::{Display, EnumString};
+
+use crate::{BookmarkFile, BookmarkRecord};
+
+mod chromium;
+mod firefox;
+mod netscape;
+
+pub use chromium::parse as parse_chromium;
+pub use firefox::read as read_firefox;
+pub use netscape::parse as parse_netscape;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ImportFormat {
+    /// Netscape `bookmarks.html` export
+    Html,
+    /// `Bookmarks` JSON file of a Chromium profile
+    Chromium,
+    /// `places.sqlite` of a Firefox profile
+    Firefox,
+}
+
+impl ImportFormat {
+    /// Guesses the format from the file name
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        match name.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("html" | "htm") => Some(Self::Html),
+            Some("json") => Some(Self::Chromium),
+            Some("sqlite") => Some(Self::Firefox),
+            _ if name == "bookmarks" => Some(Self::Chromium),
+            _ => None,
+        }
+    }
+}
+
+/// Reads every bookmark of an export or browser profile file
+#[tracing::instrument]
+pub fn read_import(path: &Path, format: ImportFormat) -> io::Result<Vec<BookmarkRecord>> {
+    match format {
+        ImportFormat::Html => Ok(parse_netscape(&fs::read_to_string(path)?)),
+        ImportFormat::Chromium => parse_chromium(&fs::read_to_string(path)?),
+        ImportFormat::Firefox => read_firefox(path),
+    }
+}
+
+/// Folder names from other tools may hold path separators, make them a single safe component.
+pub(crate) fn folder_name(name: &str) -> String {
+    let name = name.trim().replace(['/', '\\', '\0'], "-");
+    match name.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => name,
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub created: Vec<BookmarkFile>,
+    /// Urls already present in the data directory, or earlier in the same import
+    pub duplicates: Vec<BookmarkRecord>,
+    /// Records rejected by [`BookmarkRecord::validate`]
+    pub invalid: Vec<BookmarkRecord>,
+}
+
+/// Writes one file per record under `root`, skipping urls that already exist there.
+///
+/// Importing the same source again only creates the bookmarks added since.
+#[tracing::instrument(skip(records))]
+pub fn import_records<P>(
+    root: P,
+    records: impl IntoIterator<Item = BookmarkRecord>,
+) -> io::Result<ImportReport>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let root = root.as_ref();
+    let mut urls: HashSet<String> = match crate::load_from_fs(root) {
+        Ok(files) => files.map(|it| it.content.url.trim().to_string()).collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => HashSet::new(),
+        Err(e) => return Err(e),
+    };
+
+    let mut report = ImportReport::default();
+    for record in records {
+        if record.validate().is_err() {
+            report.invalid.push(record);
+        } else if !urls.insert(record.url.trim().to_string()) {
+            report.duplicates.push(record);
+        } else {
+            report.created.push(crate::create_in_fs(root, record)?);
+        }
+    }
+    tracing::debug!(
+        "Imported {}, {} duplicates, {} invalid",
+        report.created.len(),
+        report.duplicates.len(),
+        report.invalid.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(url: &str) -> BookmarkRecord {
+        BookmarkRecord {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reimport_skips_known_urls() {
+        let root = tempfile::tempdir().unwrap();
+        let first = [
+            record("https://a.example"),
+            record(" https://a.example "),
+            record("not a url"),
+            record("https://b.example"),
+        ];
+        let report = import_records(root.path(), first.clone()).unwrap();
+        assert_eq!(report.created.len(), 2);
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.invalid.len(), 1);
+
+        let again = first.into_iter().chain([record("https://c.example")]);
+        let report = import_records(root.path(), again).unwrap();
+        let created: Vec<_> = report
+            .created
+            .iter()
+            .map(|it| it.content.url.as_str())
+            .collect();
+        assert_eq!(created, ["https://c.example"]);
+        assert_eq!(report.duplicates.len(), 3);
+        assert_eq!(crate::load_from_fs(root.path()).unwrap().count(), 3);
+
+        let missing = root.path().join("new");
+        assert_eq!(
+            import_records(&missing, [record("https://a.example")])
+                .unwrap()
+                .created
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn detects_formats_by_name() {
+        let detect = |name: &str| ImportFormat::detect(Path::new(name));
+        assert_eq!(detect("export.HTML"), Some(ImportFormat::Html));
+        assert_eq!(detect("Bookmarks"), Some(ImportFormat::Chromium));
+        assert_eq!(detect("places.sqlite"), Some(ImportFormat::Firefox));
+        assert_eq!(detect("notes.txt"), None);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::BookmarkRecord;
+
+use super::folder_name;
+
+/// Replaces the entities browsers emit in bookmark exports.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let ch = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                entity
+                    if let Some(hex) = entity.strip_prefix("#x").or(entity.strip_prefix("#X")) =>
+                {
+                    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+                }
+                entity if let Some(dec) = entity.strip_prefix('#') => {
+                    dec.parse().ok().and_then(char::from_u32)
+                }
+                _ => None,
+            };
+            ch.map(|ch| (ch, end))
+        });
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Tag name (upper case) and attributes (upper case names) of `<...>` content
+fn parse_tag(tag: &str) -> (String, Vec<(String, String)>) {
+    let tag = tag.trim().trim_end_matches('/');
+    let (name, mut rest) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+    let mut attributes = vec![];
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let attribute = rest[..name_end].to_uppercase();
+        rest = rest[name_end..].trim_start();
+        let value = if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (value, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = after[1..].find(quote).map(|i| i + 1).unwrap_or(after.len());
+                    (&after[1..end], after.get(end + 1..).unwrap_or_default())
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    after.split_at(end)
+                }
+            };
+            rest = remaining;
+            decode_entities(value)
+        } else {
+            String::new()
+        };
+        attributes.push((attribute, value));
+    }
+    (name.to_uppercase(), attributes)
+}
+
+/// Text up to the next tag, and the remaining input starting at that tag
+fn take_text(input: &str) -> (String, &str) {
+    let end = input.find('<').unwrap_or(input.len());
+    (decode_entities(input[..end].trim()), &input[end..])
+}
+
+/// Parses a Netscape bookmark file, the `bookmarks.html` every browser exports.
+///
+/// Folders (`<DL><H3>`) become `BookmarkRecord.path`, the `TAGS` attribute becomes
+/// `tags` and the `<DD>` text following a bookmark becomes its `description`.
+pub fn parse(html: &str) -> Vec<BookmarkRecord> {
+    let mut records: Vec<BookmarkRecord> = vec![];
+    // One entry per open <DL>, named when it belongs to a folder
+    let mut folders: Vec<Option<String>> = vec![];
+    let mut pending_folder: Option<String> = None;
+    // A <DD> describes the bookmark right before it, folders can have one too
+    let mut describes_bookmark = false;
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map(|i| &comment[i + 3..]).unwrap_or("");
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let (name, attributes) = parse_tag(&rest[..end]);
+        rest = &rest[end + 1..];
+
+        match name.as_str() {
+            "H3" => {
+                let (text, remaining) = take_text(rest);
+                rest = remaining;
+                pending_folder = Some(folder_name(&text));
+                describes_bookmark = false;
+            }
+            "DL" => {
+                folders.push(pending_folder.take());
+                describes_bookmark = false;
+            }
+            "/DL" => {
+                folders.pop();
+                describes_bookmark = false;
+            }
+            "A" => {
+                let (title, remaining) = take_text(rest);
+                rest = remaining;
+                let attribute = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|(name, _)| name == key)
+                        .map(|(_, value)| value.trim())
+                        .filter(|value| !value.is_empty())
+                };
+                let Some(url) = attribute("HREF") else {
+                    continue;
+                };
+                let tags: Vec<String> = attribute("TAGS")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|it| !it.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                describes_bookmark = true;
+                records.push(BookmarkRecord {
+                    path: folders.iter().flatten().cloned().collect(),
+                    title: (!title.is_empty()).then_some(title),
+                    url: url.to_string(),
+                    tags: (!tags.is_empty()).then_some(tags),
+                    ..Default::default()
+                });
+            }
+            "DD" => {
+                let (text, remaining) = take_text(rest);
+                rest = remaining;
+                if describes_bookmark
+                    && let Some(record) = records.last_mut()
+                    && !text.is_empty()
+                {
+                    record.description = Some(text);
+                }
+            }
+            _ => {}
+        }
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file. <A HREF="https://comment.example">x</A> -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><A HREF="https://top.example/" ADD_DATE="1" TAGS="rust, web ,">Top &amp; first</A>
+    <DD>Described &lt;here&gt;
+    <DT><H3 ADD_DATE="2">Dev / Tools</H3>
+    <DD>Folder description, not a bookmark one
+    <DL><p>
+        <DT><A HREF="https://dev.example/?a=1&amp;b=2">Dev&#39;s &#x263A; page</A>
+        <DT><H3>Nested</H3>
+        <DL><p>
+            <DT><a href='https://nested.example' tags=deep>nested</a>
+        </DL><p>
+        <DT><A HREF="https://after-nested.example">After</A>
+    </DL><p>
+    <DT><A HREF="">No url</A>
+    <DT><A>No href</A>
+    <DT><A HREF="https://unclosed.example">Unclosed
+    <DT><H3>Open folder</H3>
+    <DL><p>
+        <DT><A HREF="https://open.example">Open</A>
+"#;
+
+    #[test]
+    fn parses_folders_tags_descriptions_and_entities() {
+        let records = parse(EXPORT);
+        let urls: Vec<_> = records.iter().map(|it| it.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://top.example/",
+                "https://dev.example/?a=1&b=2",
+                "https://nested.example",
+                "https://after-nested.example",
+                "https://unclosed.example",
+                "https://open.example",
+            ]
+        );
+
+        let top = &records[0];
+        assert!(top.path.is_empty());
+        assert_eq!(top.title.as_deref(), Some("Top & first"));
+        assert_eq!(top.tags.as_deref().unwrap(), ["rust", "web"]);
+        assert_eq!(top.description.as_deref(), Some("Described <here>"));
+
+        let dev = &records[1];
+        assert_eq!(dev.path, ["Dev - Tools"]);
+        assert_eq!(dev.title.as_deref(), Some("Dev's ☺ page"));
+        assert_eq!(dev.description, None);
+
+        assert_eq!(records[2].path, ["Dev - Tools", "Nested"]);
+        assert_eq!(records[2].tags.as_deref().unwrap(), ["deep"]);
+        assert_eq!(records[3].path, ["Dev - Tools"]);
+
+        assert!(records[4].path.is_empty());
+        assert_eq!(records[4].title.as_deref(), Some("Unclosed"));
+        assert_eq!(records[5].path, ["Open folder"]);
+    }
+
+    #[test]
+    fn decodes_only_known_entities() {
+        assert_eq!(
+            decode_entities("a &amp;&amp; b &unknown; &#65;&#X42; & c &"),
+            "a && b &unknown; AB & c &"
+        );
+        assert!(parse("").is_empty());
+        assert!(parse("<DL><DT><A HREF=\"https://cut.example\"").is_empty());
+    }
+}
@@ -0,0 +1,650 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BookmarkFile, BookmarkRecord, Diagnostic, DiagnosticKind, Match, Query, QueryField, QueryNode,
+    TomlFileIterator,
+};
+
+/// Index file name inside the data directory. Not a `.toml` file so the loader skips it.
+pub const INDEX_FILE: &str = ".bookmarks-index";
+const INDEX_VERSION: u32 = 1;
+
+/// Lower case alphanumeric words of every searchable field, tags are kept whole.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordTokens {
+    pub title: Vec<String>,
+    pub url: Vec<String>,
+    pub tags: Vec<String>,
+    pub description: Vec<String>,
+    pub path: Vec<String>,
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|it| !it.is_empty())
+        .map(str::to_lowercase)
+}
+
+impl RecordTokens {
+    pub fn new(record: &BookmarkRecord) -> Self {
+        let mut tokens = Self {
+            title: record.title.iter().flat_map(|it| words(it)).collect(),
+            url: words(&record.url).collect(),
+            tags: record
+                .tags
+                .iter()
+                .flatten()
+                .map(|it| it.to_lowercase())
+                .collect(),
+            description: record.description.iter().flat_map(|it| words(it)).collect(),
+            path: record.path.iter().flat_map(|it| words(it)).collect(),
+        };
+        for field in tokens.fields_mut() {
+            field.sort_unstable();
+            field.dedup();
+        }
+        tokens
+    }
+
+    fn fields_mut(&mut self) -> [&mut Vec<String>; 5] {
+        [
+            &mut self.title,
+            &mut self.url,
+            &mut self.tags,
+            &mut self.description,
+            &mut self.path,
+        ]
+    }
+
+    fn fields(&self) -> [&Vec<String>; 5] {
+        [
+            &self.title,
+            &self.url,
+            &self.tags,
+            &self.description,
+            &self.path,
+        ]
+    }
+}
+
+/// Position of a field in [`RecordTokens::fields`]
+fn field_slot(field: QueryField) -> usize {
+    match field {
+        QueryField::Title => 0,
+        QueryField::Url | QueryField::Host => 1,
+        QueryField::Tag => 2,
+        QueryField::Desc => 3,
+        QueryField::Path => 4,
+        QueryField::Collection => unreachable!("collections are not indexed"),
+    }
+}
+
+/// Sorted record ids
+type Postings = Vec<u32>;
+
+fn intersect(a: &[u32], b: &[u32]) -> Postings {
+    let (mut i, mut j, mut out) = (0, 0, vec![]);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+fn union<'a>(lists: impl IntoIterator<Item = &'a Postings>) -> Postings {
+    let mut out: Postings = lists.into_iter().flatten().copied().collect();
+    out.sort_unstable();
+    out.dedup();
+    out
+}
+
+/// In memory inverted index over a list of records, ids are positions in that list.
+///
+/// It only narrows the candidates down: every candidate is still scored by the
+/// [`Query`], so results are the same as with [`crate::search`].
+#[derive(Debug, Default, Clone)]
+pub struct SearchIndex {
+    tokens: Vec<RecordTokens>,
+    /// Token to records, per field
+    fields: [BTreeMap<String, Postings>; 5],
+    /// Character to records, fuzzy terms match subsequences across words
+    chars: HashMap<char, Postings>,
+}
+
+impl SearchIndex {
+    pub fn new<'a>(records: impl IntoIterator<Item = &'a BookmarkRecord>) -> Self {
+        Self::from_tokens(records.into_iter().map(RecordTokens::new))
+    }
+
+    pub fn from_tokens(tokens: impl IntoIterator<Item = RecordTokens>) -> Self {
+        let mut index = Self::default();
+        for tokens in tokens {
+            index.push(tokens);
+        }
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Appends the next record
+    pub fn push(&mut self, tokens: RecordTokens) {
+        let id = u32::try_from(self.tokens.len()).unwrap_or(u32::MAX);
+        self.link(id, &tokens);
+        self.tokens.push(tokens);
+    }
+
+    /// Replaces the tokens of record `id`
+    pub fn update(&mut self, id: usize, tokens: RecordTokens) {
+        let key = u32::try_from(id).unwrap_or(u32::MAX);
+        self.unlink(id);
+        self.link(key, &tokens);
+        self.tokens[id] = tokens;
+    }
+
+    /// Removes record `id`, later ids shift down like in a `Vec`
+    pub fn remove(&mut self, id: usize) {
+        self.unlink(id);
+        self.tokens.remove(id);
+        let id = u32::try_from(id).unwrap_or(u32::MAX);
+        let lists = self.fields.iter_mut().flat_map(|it| it.values_mut());
+        for postings in lists.chain(self.chars.values_mut()) {
+            let start = postings.partition_point(|it| *it < id);
+            for later in &mut postings[start..] {
+                *later -= 1;
+            }
+        }
+    }
+
+    /// Alphanumeric characters of all tokens, sorted and without duplicates
+    fn chars_of(tokens: &RecordTokens) -> Vec<char> {
+        let mut chars: Vec<char> = tokens
+            .fields()
+            .into_iter()
+            .flatten()
+            .flat_map(|token| token.chars().filter(|c| c.is_alphanumeric()))
+            .collect();
+        chars.sort_unstable();
+        chars.dedup();
+        chars
+    }
+
+    /// Adds `id` to the postings of `tokens`
+    fn link(&mut self, id: u32, tokens: &RecordTokens) {
+        fn insert(postings: &mut Postings, id: u32) {
+            if let Err(i) = postings.binary_search(&id) {
+                postings.insert(i, id);
+            }
+        }
+        for (slot, field) in tokens.fields().into_iter().enumerate() {
+            for token in field {
+                insert(self.fields[slot].entry(token.clone()).or_default(), id);
+            }
+        }
+        for ch in Self::chars_of(tokens) {
+            insert(self.chars.entry(ch).or_default(), id);
+        }
+    }
+
+    /// Drops record `id` from the postings of its current tokens, empty postings are removed
+    fn unlink(&mut self, id: usize) {
+        fn drop_id(postings: &mut Postings, id: u32) -> bool {
+            if let Ok(i) = postings.binary_search(&id) {
+                postings.remove(i);
+            }
+            postings.is_empty()
+        }
+        let key = u32::try_from(id).unwrap_or(u32::MAX);
+        let tokens = &self.tokens[id];
+        for (slot, field) in tokens.fields().into_iter().enumerate() {
+            for token in field {
+                if let Some(postings) = self.fields[slot].get_mut(token)
+                    && drop_id(postings, key)
+                {
+                    self.fields[slot].remove(token);
+                }
+            }
+        }
+        for ch in Self::chars_of(tokens) {
+            if let Some(postings) = self.chars.get_mut(&ch)
+                && drop_id(postings, key)
+            {
+                self.chars.remove(&ch);
+            }
+        }
+    }
+
+    /// Records that may match `node`, `None` when the index can not narrow it down
+    fn candidates(&self, node: &QueryNode) -> Option<Postings> {
+        match node {
+            QueryNode::Term { value, .. } if value.is_empty() => None,
+            QueryNode::Term {
+                field: None, value, ..
+            } => {
+                let mut chars: Vec<char> = value.chars().filter(|c| c.is_alphanumeric()).collect();
+                chars.sort_unstable();
+                chars.dedup();
+                chars.into_iter().fold(None, |acc: Option<Postings>, ch| {
+                    let postings = self.chars.get(&ch).map(Vec::as_slice).unwrap_or_default();
+                    Some(match acc {
+                        Some(acc) => intersect(&acc, postings),
+                        None => postings.to_vec(),
+                    })
+                })
+            }
+            QueryNode::Term {
+                field: Some(QueryField::Tag),
+                value,
+                ..
+            } => Some(
+                self.fields[field_slot(QueryField::Tag)]
+                    .get(value)
+                    .cloned()
+                    .unwrap_or_default(),
+            ),
+            QueryNode::Term {
+                field: Some(QueryField::Collection),
+                ..
+            } => None,
+            QueryNode::Term {
+                field: Some(field),
+                value,
+                ..
+            } => {
+                // Every word of the value is part of some word of the field
+                let vocabulary = &self.fields[field_slot(*field)];
+                words(value)
+                    .map(|word| {
+                        union(
+                            vocabulary
+                                .iter()
+                                .filter(|(token, _)| token.contains(&word))
+                                .map(|(_, postings)| postings),
+                        )
+                    })
+                    .reduce(|acc, postings| intersect(&acc, &postings))
+            }
+            QueryNode::Not(_) => None,
+            QueryNode::And(nodes) => nodes
+                .iter()
+                .filter_map(|node| self.candidates(node))
+                .reduce(|acc, postings| intersect(&acc, &postings)),
+            QueryNode::Or(nodes) => {
+                let all: Option<Vec<Postings>> =
+                    nodes.iter().map(|node| self.candidates(node)).collect();
+                all.map(|all| union(&all))
+            }
+        }
+    }
+
+    /// Ranks the records matching `query`, best first. Equal scores keep the record order.
+    ///
+    /// `record` returns the record with the given id.
+    pub fn search<'a>(
+        &self,
+        query: &Query,
+        record: impl Fn(usize) -> &'a BookmarkRecord,
+    ) -> Vec<(usize, Match)> {
+        let candidates: Box<dyn Iterator<Item = usize>> = match self.candidates(query.root()) {
+            Some(ids) => Box::new(ids.into_iter().map(|id| id as usize)),
+            None => Box::new(0..self.tokens.len()),
+        };
+        let mut keys: Vec<_> = candidates
+            .filter_map(|id| Some(id).zip(query.find(record(id))))
+            .collect();
+        keys.sort_by_key(|r| std::cmp::Reverse(r.1.score));
+        keys
+    }
+}
+
+/// A loaded file with the tokens stored for it in the index.
+#[derive(Debug, Clone)]
+pub struct IndexedFile {
+    pub file: BookmarkFile,
+    pub tokens: RecordTokens,
+}
+
+#[derive(Debug, Default)]
+pub struct IndexedLoad {
+    pub files: Vec<IndexedFile>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl IndexedLoad {
+    /// Files and the search index over them, ids are positions in the files
+    pub fn into_search(self) -> (Vec<BookmarkFile>, SearchIndex) {
+        let (files, tokens): (Vec<_>, Vec<_>) = self
+            .files
+            .into_iter()
+            .map(|it| (it.file, it.tokens))
+            .unzip();
+        (files, SearchIndex::from_tokens(tokens))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexData {
+    version: u32,
+    entries: HashMap<PathBuf, IndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    /// Modification time (seconds, nanoseconds) and size when the file was parsed
+    modified: (u64, u32),
+    len: u64,
+    record: BookmarkRecord,
+    tokens: RecordTokens,
+}
+
+fn stamp(path: &Path) -> io::Result<((u64, u32), u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok((
+        (modified.as_secs(), modified.subsec_nanos()),
+        metadata.len(),
+    ))
+}
+
+fn read_index(path: &Path) -> HashMap<PathBuf, IndexEntry> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) => {
+            if e.kind() != io::ErrorKind::NotFound {
+                tracing::warn!("Ignoring index {path:?}: {e}");
+            }
+            return HashMap::new();
+        }
+    };
+    match serde_json::from_slice::<IndexData>(&data) {
+        Ok(index) if index.version == INDEX_VERSION => index.entries,
+        Ok(_) => HashMap::new(),
+        Err(e) => {
+            tracing::warn!("Ignoring index {path:?}: {e}");
+            HashMap::new()
+        }
+    }
+}
+
+fn write_index(root: &Path, entries: HashMap<PathBuf, IndexEntry>) -> io::Result<()> {
+    let data = serde_json::to_vec(&IndexData {
+        version: INDEX_VERSION,
+        entries,
+    })?;
+    let mut tmp = tempfile::NamedTempFile::new_in(root)?;
+    io::Write::write_all(&mut tmp, &data)?;
+    tmp.persist(root.join(INDEX_FILE))?;
+    Ok(())
+}
+
+/// Like [`crate::load_with_diagnostics`], but only parses files changed since the last call.
+///
+/// Parsed records and their tokens are kept in [`INDEX_FILE`] inside the data
+/// directory, keyed by relative path and checked against the file modification
+/// time and size. Files failing to load are not indexed and reported every time.
+#[tracing::instrument]
+pub fn load_indexed<P>(path: P) -> io::Result<IndexedLoad>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let root = path.as_ref();
+    let mut cached = read_index(&root.join(INDEX_FILE));
+    let cached_len = cached.len();
+    let mut entries = HashMap::new();
+    let mut changed = 0;
+    let mut load = IndexedLoad::default();
+
+    for item in TomlFileIterator::new(root)? {
+        let item = match item {
+            Ok(item) => item,
+            Err(e) => {
+                load.diagnostics
+                    .push(Diagnostic::new(root, DiagnosticKind::Walk, &e));
+                continue;
+            }
+        };
+        let (modified, len) = match stamp(&item.path) {
+            Ok(stamp) => stamp,
+            Err(e) => {
+                load.diagnostics
+                    .push(Diagnostic::new(&item.path, DiagnosticKind::Read, &e));
+                continue;
+            }
+        };
+        let entry = match cached.remove(&item.relative_path) {
+            Some(entry) if entry.modified == modified && entry.len == len => entry,
+            _ => {
+                let content = match fs::read_to_string(&item.path) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        load.diagnostics.push(Diagnostic::new(
+                            &item.path,
+                            DiagnosticKind::Read,
+                            &e,
+                        ));
+                        continue;
+                    }
+                };
+                let mut record: BookmarkRecord = match toml::from_str(&content) {
+                    Ok(record) => record,
+                    Err(e) => {
+                        load.diagnostics
+                            .push(Diagnostic::parse(&item.path, &content, &e));
+                        continue;
+                    }
+                };
+                record.path = crate::folder_of(&item.relative_path);
+                changed += 1;
+                IndexEntry {
+                    modified,
+                    len,
+                    tokens: RecordTokens::new(&record),
+                    record,
+                }
+            }
+        };
+        let mut record = entry.record.clone();
+        record.path = crate::folder_of(&item.relative_path);
+        load.files.push(IndexedFile {
+            file: BookmarkFile {
+                content: record,
+                path: item.path,
+                relative_path: item.relative_path.clone(),
+            },
+            tokens: entry.tokens.clone(),
+        });
+        entries.insert(item.relative_path, entry);
+    }
+
+    if let Some(store) = crate::open_store(root) {
+        for indexed in &mut load.files {
+            store.attach(&mut indexed.file);
+        }
+    }
+
+    // `cached` now only holds deleted files
+    if changed > 0 || !cached.is_empty() || entries.len() != cached_len {
+        tracing::debug!("Updating index, {changed} parsed, {} dropped", cached.len());
+        if let Err(e) = write_index(root, entries) {
+            tracing::warn!("Failed to write the index: {e}");
+        }
+    }
+    Ok(load)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    fn record(title: &str, url: &str, tags: &[&str]) -> BookmarkRecord {
+        BookmarkRecord {
+            title: Some(title.to_string()),
+            url: url.to_string(),
+            tags: Some(tags.iter().map(|it| it.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    fn candidates(index: &SearchIndex, query: &str) -> Option<Postings> {
+        index.candidates(Query::parse(query).root())
+    }
+
+    fn assert_same(index: &SearchIndex, records: &[BookmarkRecord]) {
+        let rebuilt = SearchIndex::new(records);
+        assert_eq!(index.tokens, rebuilt.tokens);
+        assert_eq!(index.fields, rebuilt.fields);
+        assert_eq!(index.chars, rebuilt.chars);
+    }
+
+    #[test]
+    fn narrows_candidates() {
+        let records = [
+            record("Rust book", "https://doc.rust-lang.org/book", &["rust"]),
+            record("Go tour", "https://go.dev/tour", &["go"]),
+            record("Trust me", "https://example.com", &["misc"]),
+        ];
+        let index = SearchIndex::new(&records);
+
+        assert_eq!(candidates(&index, "tag:rust"), Some(vec![0]));
+        assert_eq!(candidates(&index, "tag:ru"), Some(vec![]));
+        assert_eq!(candidates(&index, "title:rust"), Some(vec![0, 2]));
+        assert_eq!(candidates(&index, "url:go dev"), Some(vec![1]));
+        assert_eq!(
+            candidates(&index, "title:rust OR tag:go"),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(candidates(&index, "tag:rust title:trust"), Some(vec![]));
+        // Fuzzy terms only need the characters
+        assert_eq!(candidates(&index, "dv"), Some(vec![1]));
+        assert_eq!(candidates(&index, "-tag:go"), None);
+        assert_eq!(candidates(&index, "tag:go OR -tag:go"), None);
+        assert_eq!(candidates(&index, ""), None);
+
+        let query = Query::parse("title:rust");
+        let ranked: Vec<usize> = index
+            .search(&query, |id| &records[id])
+            .into_iter()
+            .map(|it| it.0)
+            .collect();
+        assert_eq!(ranked, [0, 2]);
+    }
+
+    #[test]
+    fn update_and_remove_match_a_rebuild() {
+        let mut records = vec![
+            record("Rust book", "https://doc.rust-lang.org/book", &["rust"]),
+            record("Go tour", "https://go.dev/tour", &["go"]),
+            record("Trust me", "https://example.com", &["misc"]),
+            record("Zig", "https://ziglang.org", &["zig", "rust"]),
+        ];
+        let mut index = SearchIndex::new(&records);
+
+        records[1] = record("Python", "https://python.org", &["py"]);
+        index.update(1, RecordTokens::new(&records[1]));
+        assert_same(&index, &records);
+        assert_eq!(candidates(&index, "tag:go"), Some(vec![]));
+        assert_eq!(candidates(&index, "tag:py"), Some(vec![1]));
+
+        records.remove(0);
+        index.remove(0);
+        assert_same(&index, &records);
+        assert_eq!(candidates(&index, "tag:rust"), Some(vec![2]));
+
+        records.remove(2);
+        index.remove(2);
+        index.push(RecordTokens::new(&records[1]));
+        records.push(records[1].clone());
+        assert_same(&index, &records);
+        assert_eq!(candidates(&index, "title:trust"), Some(vec![1, 2]));
+    }
+
+    fn write(path: &Path, content: &str, modified: SystemTime) {
+        fs::write(path, content).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    fn titles(root: &Path) -> Vec<String> {
+        let mut titles: Vec<String> = load_indexed(root)
+            .unwrap()
+            .files
+            .into_iter()
+            .filter_map(|it| it.file.content.title)
+            .collect();
+        titles.sort();
+        titles
+    }
+
+    #[test]
+    fn load_indexed_reparses_changed_files() {
+        let root = tempfile::tempdir().unwrap();
+        let (a, b) = (root.path().join("a.toml"), root.path().join("b.toml"));
+        let then = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        write(&a, "title = \"aaa\"\nurl = \"https://a\"\n", then);
+        write(&b, "title = \"bbb\"\nurl = \"https://b\"\n", then);
+        assert_eq!(titles(root.path()), ["aaa", "bbb"]);
+        assert_eq!(read_index(&root.path().join(INDEX_FILE)).len(), 2);
+
+        // Same time and size: the stored record is used
+        write(&a, "title = \"AAA\"\nurl = \"https://a\"\n", then);
+        assert_eq!(titles(root.path()), ["aaa", "bbb"]);
+
+        // A newer modification time
+        write(
+            &a,
+            "title = \"AAA\"\nurl = \"https://a\"\n",
+            then + Duration::from_secs(1),
+        );
+        assert_eq!(titles(root.path()), ["AAA", "bbb"]);
+
+        // Another size with the same time
+        write(&b, "title = \"bbbb\"\nurl = \"https://b\"\n", then);
+        assert_eq!(titles(root.path()), ["AAA", "bbbb"]);
+
+        // Deleted files are dropped from the index
+        fs::remove_file(&b).unwrap();
+        assert_eq!(titles(root.path()), ["AAA"]);
+        let entries = read_index(&root.path().join(INDEX_FILE));
+        assert_eq!(entries.keys().collect::<Vec<_>>(), [Path::new("a.toml")]);
+
+        // A corrupt index is ignored and written again
+        fs::write(root.path().join(INDEX_FILE), "{ not json").unwrap();
+        assert_eq!(titles(root.path()), ["AAA"]);
+        assert_eq!(read_index(&root.path().join(INDEX_FILE)).len(), 1);
+
+        // Files failing to parse are reported and not indexed
+        fs::write(root.path().join("broken.toml"), "url = ").unwrap();
+        let load = load_indexed(root.path()).unwrap();
+        assert_eq!(load.files.len(), 1);
+        assert_eq!(load.diagnostics.len(), 1);
+        assert_eq!(read_index(&root.path().join(INDEX_FILE)).len(), 1);
+    }
+}
@@ -1,16 +1,45 @@
 use std::{
-    fmt, fs, io,
+    ffi::OsString,
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
     path::{Path, PathBuf},
 };
 
-use fuzzy_matcher::FuzzyMatcher;
-use fuzzy_matcher::skim::SkimMatcherV2;
-
 use serde::{Deserialize, Serialize};
 
+mod dedupe;
+mod diagnostic;
+mod embed;
+mod embedding_store;
+mod export;
+mod file_name;
+mod import;
+mod index;
+mod query;
+mod tags;
 mod toml_file_iterator;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub use dedupe::{
+    DuplicateGroup, find_duplicates, merge_duplicate_records, merge_duplicates, normalize_url,
+};
+pub use diagnostic::{Diagnostic, DiagnosticKind};
+pub use embed::{
+    CommandEmbedder, Embedder, HashingEmbedder, cosine_similarity, embedding_text, hybrid_search,
+    semantic_search,
+};
+pub use embedding_store::{EMBEDDINGS_FILE, EmbeddingStore};
+pub use export::{ExportFilter, ExportFormat, export};
+pub use import::{
+    ImportFormat, ImportReport, import_records, parse_chromium, parse_netscape, read_firefox,
+    read_import,
+};
+pub use index::{INDEX_FILE, IndexedFile, IndexedLoad, RecordTokens, SearchIndex, load_indexed};
+pub use query::{FieldWeights, Highlights, Match, MatchField, Query, QueryField, QueryNode};
+pub use tags::{delete_tags, rename_tags, retag, retag_all, tag_counts};
+pub use toml_file_iterator::{TomlFileIterator, TomlFileIteratorItem};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct BookmarkRecord {
     #[serde(skip)]
     pub path: Vec<String>,
@@ -19,6 +48,9 @@ pub struct BookmarkRecord {
     pub tags: Option<Vec<String>>,
     pub description: Option<String>,
     pub embeddings: Option<Vec<f32>>,
+    /// Name of the collection (data directory) the record belongs to, set by the application
+    #[serde(skip)]
+    pub collection: Option<String>,
 }
 
 impl BookmarkRecord {
@@ -43,6 +75,29 @@ impl BookmarkRecord {
 
         parts.join(" ").to_lowercase()
     }
+
+    /// Checks that the record can be written and read back
+    pub fn validate(&self) -> io::Result<()> {
+        let invalid = |msg: &str| Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        let url = self.url.trim();
+        if url.is_empty() {
+            return invalid("Url is required");
+        }
+        if url.chars().any(char::is_whitespace) {
+            return invalid("Url must not contain whitespace");
+        }
+        match url.split_once(':') {
+            Some((scheme, _))
+                if scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')) =>
+            {
+                Ok(())
+            }
+            _ => invalid("Url must start with a scheme, e.g. https://"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,70 +112,532 @@ pub fn load_from_fs<P>(path: P) -> io::Result<impl Iterator<Item = BookmarkFile>
 where
     P: AsRef<Path> + fmt::Debug,
 {
-    let toml_path_iterator = toml_file_iterator::TomlFileIterator::new(&path)?;
-    let files = toml_path_iterator.filter_map(|path_result| match path_result {
-        Ok(entry) => {
-            let file = match fs::read_to_string(&entry.path) {
-                Ok(file) => file,
-                Err(e) => {
-                    tracing::warn!("Failed to read {entry:?}. {e}");
-                    return None;
-                }
-            };
-
-            let mut content: BookmarkRecord = match toml::from_str(&file) {
-                Ok(content) => content,
-                Err(e) => {
-                    tracing::warn!("Failed to parse {entry:?}. {e}");
-                    return None;
-                }
-            };
-
-            content.path = entry
-                .relative_path
-                .iter()
-                .map(|it| it.to_str().unwrap_or_default().to_string())
-                .collect();
-            content.path.pop();
-
-            tracing::trace!("Processed {entry:?}. {content:?}");
-            Some(BookmarkFile {
-                path: entry.path,
-                relative_path: entry.relative_path,
-                content,
-            })
-        }
-        Err(e) => {
-            tracing::warn!("Failed {e}");
+    let files = load_with_diagnostics(path)?.filter_map(|result| match result {
+        Ok(file) => Some(file),
+        Err(diagnostic) => {
+            tracing::warn!("{diagnostic}");
             None
         }
     });
     Ok(files)
 }
 
+/// Like [`load_from_fs`], but yields a [`Diagnostic`] for every file that failed to load.
 #[tracing::instrument]
+pub fn load_with_diagnostics<P>(
+    path: P,
+) -> io::Result<impl Iterator<Item = Result<BookmarkFile, Diagnostic>>>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let root = path.as_ref().to_path_buf();
+    let toml_path_iterator = toml_file_iterator::TomlFileIterator::new(&path)?;
+    let store = open_store(&root);
+    let files = toml_path_iterator.map(move |path_result| {
+        let entry = path_result.map_err(|e| Diagnostic::new(&root, DiagnosticKind::Walk, &e))?;
+        let file = fs::read_to_string(&entry.path)
+            .map_err(|e| Diagnostic::new(&entry.path, DiagnosticKind::Read, &e))?;
+
+        let mut content: BookmarkRecord =
+            toml::from_str(&file).map_err(|e| Diagnostic::parse(&entry.path, &file, &e))?;
+
+        content.path = folder_of(&entry.relative_path);
+
+        tracing::trace!("Processed {entry:?}. {content:?}");
+        let mut file = BookmarkFile {
+            path: entry.path,
+            relative_path: entry.relative_path,
+            content,
+        };
+        if let Some(store) = &store {
+            store.attach(&mut file);
+        }
+        Ok(file)
+    });
+    Ok(files)
+}
+
+/// Loads every file and collects problems, including records failing [`BookmarkRecord::validate`].
+#[tracing::instrument]
+pub fn check<P>(path: P) -> io::Result<Vec<Diagnostic>>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    Ok(load_with_diagnostics(path)?
+        .filter_map(|result| match result {
+            Ok(file) => file
+                .content
+                .validate()
+                .err()
+                .map(|e| Diagnostic::new(&file.path, DiagnosticKind::Invalid, &e)),
+            Err(diagnostic) => Some(diagnostic),
+        })
+        .collect())
+}
+
+/// Sidecar embeddings of the data directory, a broken store is logged and ignored
+fn open_store(root: &Path) -> Option<EmbeddingStore> {
+    EmbeddingStore::open(root).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring embeddings: {e}");
+        None
+    })
+}
+
+/// Data directory of a loaded bookmark
+fn data_root(bookmark: &BookmarkFile) -> io::Result<PathBuf> {
+    let depth = bookmark.relative_path.components().count();
+    bookmark
+        .path
+        .ancestors()
+        .nth(depth)
+        .map(Path::to_path_buf)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Unknown data root"))
+}
+
+fn folder_of(relative_path: &Path) -> Vec<String> {
+    let mut folder: Vec<String> = relative_path
+        .iter()
+        .map(|it| it.to_str().unwrap_or_default().to_string())
+        .collect();
+    folder.pop();
+    folder
+}
+
+/// Reads a single bookmark file located under `root`.
+///
+/// `path` may be absolute or relative to either `root` or the working directory.
+#[tracing::instrument]
+pub fn load_file<R, P>(root: R, path: P) -> io::Result<BookmarkFile>
+where
+    R: AsRef<Path> + fmt::Debug,
+    P: AsRef<Path> + fmt::Debug,
+{
+    let (root, path) = (root.as_ref(), path.as_ref());
+    let full_path = if path.is_relative() && root.join(path).is_file() {
+        root.join(path)
+    } else {
+        path.to_path_buf()
+    };
+    let relative_path = full_path
+        .canonicalize()?
+        .strip_prefix(root.canonicalize()?)
+        .map(Path::to_path_buf)
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{path:?} is not inside {root:?}"),
+            )
+        })?;
+
+    let mut content = from_toml_str(&fs::read_to_string(&full_path)?)?;
+    content.path = folder_of(&relative_path);
+    let mut file = BookmarkFile {
+        path: root.join(&relative_path),
+        relative_path,
+        content,
+    };
+    if let Some(store) = open_store(root) {
+        store.attach(&mut file);
+    }
+    Ok(file)
+}
+
+pub fn from_toml_str(content: &str) -> io::Result<BookmarkRecord> {
+    toml::from_str(content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+pub fn to_toml_string(record: &BookmarkRecord) -> io::Result<String> {
+    toml::to_string_pretty(record).map_err(|err| io::Error::other(err.to_string()))
+}
+
+/// Writes the bookmark file.
+///
+/// With an [`EmbeddingStore`] in the data directory the embeddings stay out of
+/// the file: vectors the store does not hold yet are added to it.
+#[tracing::instrument(skip(bookmark), fields(path = ?bookmark.path))]
 pub fn save_to_fs(bookmark: &BookmarkFile) -> io::Result<()> {
-    let str_content = toml::to_string_pretty(&bookmark.content)
-        .map_err(|err| io::Error::other(err.to_string()))?;
+    let store = match &bookmark.content.embeddings {
+        Some(_) => data_root(bookmark)
+            .ok()
+            .and_then(|root| EmbeddingStore::open(&root).transpose())
+            .transpose()?,
+        None => None,
+    };
+    let str_content = match (store, &bookmark.content.embeddings) {
+        (Some(mut store), Some(vector)) => {
+            if !store.holds(&bookmark.relative_path, vector) {
+                store.insert(bookmark, vector.clone());
+                store.save()?;
+            }
+            to_toml_string(&BookmarkRecord {
+                embeddings: None,
+                ..bookmark.content.clone()
+            })?
+        }
+        _ => to_toml_string(&bookmark.content)?,
+    };
     fs::write(&bookmark.path, str_content)
 }
 
+/// Writes a new bookmark into the `record.path` folder under `root`.
+///
+/// The file name is derived from the title (or url) and never replaces an
+/// existing file: on collision a numeric suffix is added.
+#[tracing::instrument]
+pub fn create_in_fs<P>(root: P, mut record: BookmarkRecord) -> io::Result<BookmarkFile>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    record.validate()?;
+    record.url = record.url.trim().to_string();
+    record.path = file_name::check_folder(&record.path)?;
+    let root = root.as_ref();
+    let dir = file_name::folder_path(root, &record.path)?;
+    fs::create_dir_all(&dir)?;
+
+    let str_content = to_toml_string(&record)?;
+    let stem = file_name::stem_for(&record);
+    for attempt in 1.. {
+        let path = dir.join(file_name::candidate(&stem, attempt));
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        file.write_all(str_content.as_bytes())?;
+        let relative_path = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+        tracing::debug!("Created {path:?}");
+        return Ok(BookmarkFile {
+            content: record,
+            path,
+            relative_path,
+        });
+    }
+    unreachable!()
+}
+
+/// Ranks the records matching the [`Query`] syntax in `needle`, best first,
+/// with the matched characters to highlight.
 #[tracing::instrument(skip(records))]
 pub fn search<'a>(
     needle: &str,
     records: impl IntoIterator<Item = &'a BookmarkRecord>,
-) -> impl Iterator<Item = (usize, i64)> {
-    let needle = needle.to_lowercase();
-    let matcher = SkimMatcherV2::default();
+) -> impl Iterator<Item = (usize, Match)> {
+    search_query(&Query::parse(needle), records)
+}
+
+/// Like [`search`] for an already parsed query. Equal scores keep the record order.
+pub fn search_query<'a>(
+    query: &Query,
+    records: impl IntoIterator<Item = &'a BookmarkRecord>,
+) -> std::vec::IntoIter<(usize, Match)> {
     let mut keys: Vec<_> = records
         .into_iter()
         .enumerate()
-        .filter_map(|r| {
-            let fuzz = r.1.fuzzy_string();
-            Some(r.0).zip(matcher.fuzzy_match(&fuzz, &needle))
-        })
+        .filter_map(|(i, record)| Some(i).zip(query.find(record)))
         .collect();
-    tracing::trace!("Fuzzied items {:?}", keys);
-    keys.sort_unstable_by_key(|r| r.1);
-    keys.into_iter().rev()
+    tracing::trace!("Matched items {:?}", keys);
+    keys.sort_by_key(|r| std::cmp::Reverse(r.1.score));
+    keys.into_iter()
+}
+
+/// Removes the bookmark file from disk, along with its stored embeddings.
+#[tracing::instrument]
+pub fn delete_from_fs(bookmark: &BookmarkFile) -> io::Result<()> {
+    fs::remove_file(&bookmark.path)?;
+    if let Some(mut store) = EmbeddingStore::open(&data_root(bookmark)?)? {
+        store.retain(|it| it != bookmark.relative_path);
+        store.save()?;
+    }
+    Ok(())
+}
+
+/// Moves the bookmark file into `folder` (relative to the data root), keeping its name.
+///
+/// Fails if a file with the same name already exists in the target folder.
+#[tracing::instrument]
+pub fn move_in_fs(bookmark: &mut BookmarkFile, folder: &[String]) -> io::Result<()> {
+    let file_name = bookmark
+        .path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Missing file name"))?
+        .to_owned();
+    relocate(bookmark, file_name::check_folder(folder)?, file_name)
+}
+
+/// Renames the bookmark file inside its folder. The `.toml` extension is added when missing.
+///
+/// Fails if a file with the new name already exists.
+#[tracing::instrument]
+pub fn rename_in_fs(bookmark: &mut BookmarkFile, name: &str) -> io::Result<()> {
+    let name = file_name::check_component(name)?;
+    let name = file_name::check_stem(name.strip_suffix(".toml").unwrap_or(name))?;
+    let folder = bookmark.content.path.clone();
+    relocate(bookmark, folder, format!("{name}.toml").into())
+}
+
+/// Moves the bookmark file into `folder` of another data directory `root`, keeping its name.
+///
+/// The old data directory forgets its stored embeddings, the new one gets them on the next save.
+/// Fails if a file with the same name already exists in the target folder.
+#[tracing::instrument]
+pub fn move_to_root(bookmark: &mut BookmarkFile, root: &Path, folder: &[String]) -> io::Result<()> {
+    let file_name = bookmark
+        .path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Missing file name"))?
+        .to_owned();
+    relocate_to(bookmark, root, file_name::check_folder(folder)?, file_name)
+}
+
+fn relocate(
+    bookmark: &mut BookmarkFile,
+    folder: Vec<String>,
+    file_name: OsString,
+) -> io::Result<()> {
+    let root = data_root(bookmark)?;
+    relocate_to(bookmark, &root, folder, file_name)
+}
+
+fn relocate_to(
+    bookmark: &mut BookmarkFile,
+    root: &Path,
+    folder: Vec<String>,
+    file_name: OsString,
+) -> io::Result<()> {
+    let old_root = data_root(bookmark)?;
+    let dir = file_name::folder_path(root, &folder)?;
+    let path = dir.join(file_name);
+    if path == bookmark.path {
+        return Ok(());
+    }
+    fs::create_dir_all(&dir)?;
+    move_file(&bookmark.path, &path)?;
+    tracing::debug!("Moved {:?} to {path:?}", bookmark.path);
+
+    let relative_path = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+    if let Some(mut store) = EmbeddingStore::open(&old_root)? {
+        if old_root == root {
+            store.rename(&bookmark.relative_path, &relative_path);
+        } else {
+            store.retain(|it| it != bookmark.relative_path);
+        }
+        store.save()?;
+    }
+    bookmark.relative_path = relative_path;
+    bookmark.path = path;
+    bookmark.content.path = folder;
+    Ok(())
+}
+
+/// Moves `from` to `to`, failing instead of replacing a file created there meanwhile
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::hard_link(from, to) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{to:?} already exists"),
+            ));
+        }
+        // Data directories may live on different file systems, some without hard links
+        Err(_) => {
+            let mut target = OpenOptions::new().write(true).create_new(true).open(to)?;
+            io::copy(&mut fs::File::open(from)?, &mut target)?;
+        }
+    }
+    fs::remove_file(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(title: &str, tags: &[&str]) -> BookmarkRecord {
+        BookmarkRecord {
+            title: Some(title.to_string()),
+            url: format!("https://example.com/{}", title.replace(' ', "-")),
+            tags: Some(tags.iter().map(|it| it.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn create_adds_suffixes_and_stays_in_root() {
+        let root = tempfile::tempdir().unwrap();
+        let mut folder = record("Rust book", &[]);
+        folder.path = vec!["reading".to_string(), " notes ".to_string()];
+        let names: Vec<_> = (0..3)
+            .map(|_| create_in_fs(root.path(), folder.clone()).unwrap())
+            .map(|it| it.relative_path)
+            .collect();
+        let dir = Path::new("reading").join("notes");
+        assert_eq!(
+            names,
+            [
+                dir.join("rust-book.toml"),
+                dir.join("rust-book-2.toml"),
+                dir.join("rust-book-3.toml")
+            ]
+        );
+
+        let mut untitled = record("", &[]);
+        untitled.title = None;
+        untitled.url = "https://example.com/a?b".to_string();
+        let file = create_in_fs(root.path(), untitled).unwrap();
+        assert_eq!(file.relative_path, Path::new("example-com-a-b.toml"));
+
+        for escape in ["..", "/etc", "a/../..", "."] {
+            let mut escaping = record("Escape", &[]);
+            escaping.path = vec![escape.to_string()];
+            let err = create_in_fs(root.path(), escaping).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{escape}");
+        }
+        let mut invalid = record("No scheme", &[]);
+        invalid.url = "example.com".to_string();
+        assert!(create_in_fs(root.path(), invalid).is_err());
+        assert_eq!(load_from_fs(root.path()).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn move_rename_and_delete_never_replace_files() {
+        let root = tempfile::tempdir().unwrap();
+        let mut file = create_in_fs(root.path(), record("Rust book", &[])).unwrap();
+        let mut taken = record("Rust book", &[]);
+        taken.path = vec!["taken".to_string()];
+        let taken = create_in_fs(root.path(), taken).unwrap();
+        let other = create_in_fs(root.path(), record("Other", &[])).unwrap();
+
+        let err = move_in_fs(&mut file, &["taken".to_string()]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(file.path.exists());
+        assert_eq!(
+            load_file(root.path(), &taken.relative_path)
+                .unwrap()
+                .content
+                .path,
+            ["taken"]
+        );
+
+        move_in_fs(&mut file, &["reading".to_string()]).unwrap();
+        assert_eq!(
+            file.relative_path,
+            Path::new("reading").join("rust-book.toml")
+        );
+        assert!(!root.path().join("rust-book.toml").exists());
+        assert_eq!(
+            load_file(root.path(), &file.relative_path)
+                .unwrap()
+                .content
+                .path,
+            ["reading"]
+        );
+
+        move_in_fs(&mut file, &[]).unwrap();
+        let err = rename_in_fs(&mut file, "other").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(other.path.exists());
+        rename_in_fs(&mut file, "renamed.toml").unwrap();
+        assert_eq!(file.relative_path, Path::new("renamed.toml"));
+        assert!(rename_in_fs(&mut file, "../escape").is_err());
+        // Names the loader would skip
+        for hidden in [".toml", " .toml", ".hidden", ".hidden.toml"] {
+            let err = rename_in_fs(&mut file, hidden).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{hidden:?}");
+        }
+        assert_eq!(file.relative_path, Path::new("renamed.toml"));
+
+        delete_from_fs(&file).unwrap();
+        assert!(!file.path.exists());
+        assert!(delete_from_fs(&file).is_err());
+        assert_eq!(load_from_fs(root.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn diagnostics_locate_errors_without_aborting_the_load() {
+        let root = tempfile::tempdir().unwrap();
+        create_in_fs(root.path(), record("Good", &[])).unwrap();
+        let broken = root.path().join("broken.toml");
+        fs::write(&broken, "title = \"Broken\"\nurl = https://example.com\n").unwrap();
+        fs::create_dir(root.path().join("sub")).unwrap();
+        let invalid = root.path().join("sub").join("invalid.toml");
+        fs::write(&invalid, "url = \"not a url\"\n").unwrap();
+
+        let (loaded, failed): (Vec<_>, Vec<_>) = load_with_diagnostics(root.path())
+            .unwrap()
+            .partition(Result::is_ok);
+        assert_eq!(loaded.len(), 2);
+        let [Err(diagnostic)] = failed.as_slice() else {
+            panic!("{failed:?}");
+        };
+        assert_eq!(diagnostic.path, broken);
+        assert_eq!(diagnostic.kind, DiagnosticKind::Parse);
+        assert_eq!(diagnostic.location, Some((2, 7)));
+        assert!(diagnostic.to_string().contains("broken.toml:2:7: parse: "));
+
+        let mut diagnostics = check(root.path()).unwrap();
+        diagnostics.sort_by(|a, b| a.path.cmp(&b.path));
+        let kinds: Vec<_> = diagnostics.iter().map(|it| (&it.path, it.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (&broken, DiagnosticKind::Parse),
+                (&invalid, DiagnosticKind::Invalid)
+            ]
+        );
+        assert_eq!(diagnostics[1].location, None);
+        assert_eq!(load_from_fs(root.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn weighted_search_with_highlights() {
+        let mut by_url = record("Gardening", &[]);
+        by_url.url = "https://docs.rs/".to_string();
+        let records = [by_url, record("Docs", &["reference"])];
+
+        let ranked: Vec<_> = search("docs", &records).collect();
+        assert_eq!(ranked.iter().map(|it| it.0).collect::<Vec<_>>(), [1, 0]);
+        assert_eq!(ranked[0].1.highlights.get(MatchField::Title), [0, 1, 2, 3]);
+        assert_eq!(ranked[1].1.highlights.get(MatchField::Url), [8, 9, 10, 11]);
+
+        let title_only = FieldWeights {
+            url: 0,
+            ..Default::default()
+        };
+        let found = Query::parse("docs")
+            .with_weights(title_only)
+            .find(&records[0]);
+        assert_eq!(found.map(|it| it.score), Some(0));
+
+        let found = Query::parse("tag:REFERENCE -tag:other")
+            .find(&records[1])
+            .unwrap();
+        assert_eq!(found.highlights.get(MatchField::Tag(0)).len(), 9);
+        assert!(found.highlights.get(MatchField::Title).is_empty());
+    }
+
+    #[test]
+    fn move_between_collections() {
+        let (personal, work) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let mut file = create_in_fs(personal.path(), record("Rust book", &["rust"])).unwrap();
+        let name = file.path.file_name().unwrap().to_owned();
+
+        move_to_root(&mut file, work.path(), &["reading".to_string()]).unwrap();
+        assert!(!personal.path().join(&name).exists());
+        assert_eq!(file.relative_path, Path::new("reading").join(&name));
+        let loaded = load_file(work.path(), &file.relative_path).unwrap();
+        assert_eq!(loaded.content.path, ["reading"]);
+
+        file.content.collection = Some("Work".to_string());
+        assert!(
+            Query::parse("collection:work")
+                .find(&file.content)
+                .is_some()
+        );
+        assert!(
+            Query::parse("-collection:work")
+                .find(&file.content)
+                .is_none()
+        );
+    }
 }
@@ -0,0 +1,710 @@
+use std::{collections::BTreeMap, fmt};
+
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use strum::{Display, EnumString};
+
+use crate::BookmarkRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum QueryField {
+    Tag,
+    Path,
+    Url,
+    Host,
+    Title,
+    #[strum(serialize = "desc", serialize = "description")]
+    Desc,
+    /// Name of the collection the record was loaded from, see [`BookmarkRecord::collection`]
+    Collection,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryNode {
+    /// `value`, `"a phrase"` or `field:value`, the value is lower case
+    Term {
+        field: Option<QueryField>,
+        value: String,
+        phrase: bool,
+    },
+    Not(Box<QueryNode>),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Term(QueryNode),
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    let quoted = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        // Runs to the end of the input while the closing quote is not typed yet
+        let mut value = String::new();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' => break,
+                '\\' if let Some(escaped) = chars.next_if(|c| matches!(c, '"' | '\\')) => {
+                    value.push(escaped);
+                }
+                ch => value.push(ch),
+            }
+        }
+        value.to_lowercase()
+    };
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '|' => {
+                chars.next();
+                tokens.push(match ch {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Or,
+                });
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Term(QueryNode::Term {
+                    field: None,
+                    value: quoted(&mut chars),
+                    phrase: true,
+                }));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek()
+                    && !ch.is_whitespace()
+                    && !matches!(ch, '(' | ')' | '"')
+                {
+                    word.push(ch);
+                    chars.next();
+                }
+                let field = word
+                    .split_once(':')
+                    .and_then(|(field, _)| field.to_lowercase().parse::<QueryField>().ok());
+                tokens.push(match (word.as_str(), field) {
+                    ("AND", _) => Token::And,
+                    ("OR", _) => Token::Or,
+                    ("NOT", _) => Token::Not,
+                    (_, Some(field)) => {
+                        let (_, value) = word.split_once(':').unwrap_or_default();
+                        let phrase = value.is_empty() && chars.peek() == Some(&'"');
+                        if phrase {
+                            chars.next();
+                        }
+                        Token::Term(QueryNode::Term {
+                            field: Some(field),
+                            value: if phrase {
+                                quoted(&mut chars)
+                            } else {
+                                value.to_lowercase()
+                            },
+                            phrase,
+                        })
+                    }
+                    (_, None) => Token::Term(QueryNode::Term {
+                        field: None,
+                        value: word.to_lowercase(),
+                        phrase: false,
+                    }),
+                });
+            }
+        }
+    }
+    tokens
+}
+
+/// Recursive descent over the tokens, lenient so a query that is still being typed works
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> QueryNode {
+        let mut nodes = vec![self.and()];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            nodes.push(self.and());
+        }
+        simplify(nodes, QueryNode::Or)
+    }
+
+    fn and(&mut self) -> QueryNode {
+        let mut nodes = vec![];
+        loop {
+            match self.peek() {
+                None | Some(Token::Or | Token::Close) => break,
+                Some(Token::And) => self.pos += 1,
+                Some(_) => nodes.extend(self.unary()),
+            }
+        }
+        simplify(nodes, QueryNode::And)
+    }
+
+    fn unary(&mut self) -> Option<QueryNode> {
+        let token = self.tokens.get(self.pos).cloned()?;
+        self.pos += 1;
+        match token {
+            Token::Not => {
+                let node = self.unary()?;
+                Some(QueryNode::Not(Box::new(node)))
+            }
+            Token::Open => {
+                let node = self.or();
+                if self.peek() == Some(&Token::Close) {
+                    self.pos += 1;
+                }
+                Some(node)
+            }
+            Token::Term(node) => Some(node),
+            Token::Close | Token::And | Token::Or => None,
+        }
+    }
+}
+
+fn simplify(mut nodes: Vec<QueryNode>, wrap: fn(Vec<QueryNode>) -> QueryNode) -> QueryNode {
+    if nodes.len() == 1 {
+        nodes.remove(0)
+    } else {
+        wrap(nodes)
+    }
+}
+
+/// Host part of an url, without user info and port
+fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map(|(_, host)| host)
+        .unwrap_or(authority);
+    match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    }
+}
+
+/// Multipliers of the fuzzy score by the field a term matched in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldWeights {
+    pub title: i64,
+    pub tags: i64,
+    pub path: i64,
+    pub url: i64,
+    pub description: i64,
+}
+
+impl Default for FieldWeights {
+    fn default() -> Self {
+        Self {
+            title: 5,
+            tags: 4,
+            path: 3,
+            url: 2,
+            description: 1,
+        }
+    }
+}
+
+/// A field of a record, tags and folders by position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MatchField {
+    Title,
+    Tag(usize),
+    Path(usize),
+    Url,
+    Description,
+}
+
+/// Matched character indices (not bytes) per field, sorted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Highlights(BTreeMap<MatchField, Vec<usize>>);
+
+impl Highlights {
+    pub fn get(&self, field: MatchField) -> &[usize] {
+        self.0.get(&field).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (MatchField, &[usize])> {
+        self.0
+            .iter()
+            .map(|(field, indices)| (*field, indices.as_slice()))
+    }
+
+    fn add(&mut self, field: MatchField, indices: impl IntoIterator<Item = usize>) {
+        let entry = self.0.entry(field).or_default();
+        entry.extend(indices);
+        entry.sort_unstable();
+        entry.dedup();
+    }
+
+    fn merge(&mut self, other: Highlights) {
+        for (field, indices) in other.0 {
+            self.add(field, indices);
+        }
+    }
+}
+
+/// Score and highlights of a matching record
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub score: i64,
+    pub highlights: Highlights,
+}
+
+/// Character range of the first case insensitive occurrence of `needle` (lower case)
+fn find_ignore_case(text: &str, needle: &str) -> Option<std::ops::Range<usize>> {
+    let text: Vec<char> = text.chars().collect();
+    let needle: Vec<char> = needle.chars().collect();
+    if needle.is_empty() || needle.len() > text.len() {
+        return None;
+    }
+    (0..=text.len() - needle.len())
+        .find(|start| {
+            text[*start..]
+                .iter()
+                .zip(&needle)
+                .all(|(a, b)| a.to_lowercase().eq(std::iter::once(*b)))
+        })
+        .map(|start| start..start + needle.len())
+}
+
+/// Parsed search query.
+///
+/// Whitespace separated terms must all match, `OR` (or `|`) and parentheses
+/// combine alternatives, `-term` or `NOT term` negates. A term is either fuzzy
+/// matched against every field, a `"quoted phrase"` that must appear as is in
+/// one field, or qualified by a field: `tag:rust`, `path:work`, `url:docs`,
+/// `host:example.com`, `title:"async rust"`, `desc:guide`, `collection:work`.
+/// Inside quotes `\"` and `\\` stand for a quote and a backslash.
+///
+/// Fuzzy scores are multiplied by the [`FieldWeights`] of the best matching field.
+pub struct Query {
+    root: QueryNode,
+    weights: FieldWeights,
+    matcher: SkimMatcherV2,
+}
+
+impl fmt::Debug for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Query")
+            .field("root", &self.root)
+            .field("weights", &self.weights)
+            .finish()
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let mut parser = Parser {
+            tokens: tokenize(input),
+            pos: 0,
+        };
+        let mut nodes = vec![];
+        while parser.peek().is_some() {
+            nodes.push(parser.or());
+            // Skips a stray `)`
+            parser.pos += 1;
+        }
+        Self {
+            root: simplify(nodes, QueryNode::And),
+            weights: FieldWeights::default(),
+            matcher: SkimMatcherV2::default().ignore_case(),
+        }
+    }
+
+    pub fn with_weights(mut self, weights: FieldWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    pub fn root(&self) -> &QueryNode {
+        &self.root
+    }
+
+    /// Relevance of `record`, `None` when it does not match
+    pub fn score(&self, record: &BookmarkRecord) -> Option<i64> {
+        self.eval(&self.root, record, None)
+    }
+
+    pub fn matches(&self, record: &BookmarkRecord) -> bool {
+        self.score(record).is_some()
+    }
+
+    /// Like [`Query::score`], with the matched characters of every field
+    pub fn find(&self, record: &BookmarkRecord) -> Option<Match> {
+        let mut highlights = Highlights::default();
+        let score = self.eval(&self.root, record, Some(&mut highlights))?;
+        Some(Match { score, highlights })
+    }
+
+    fn fields<'a>(&self, record: &'a BookmarkRecord) -> Vec<(MatchField, &'a str, i64)> {
+        let weights = self.weights;
+        let mut fields = vec![];
+        fields.extend(
+            record
+                .title
+                .as_deref()
+                .map(|it| (MatchField::Title, it, weights.title)),
+        );
+        for (i, tag) in record.tags.iter().flatten().enumerate() {
+            fields.push((MatchField::Tag(i), tag.as_str(), weights.tags));
+        }
+        for (i, folder) in record.path.iter().enumerate() {
+            fields.push((MatchField::Path(i), folder.as_str(), weights.path));
+        }
+        fields.push((MatchField::Url, record.url.as_str(), weights.url));
+        fields.extend(
+            record
+                .description
+                .as_deref()
+                .map(|it| (MatchField::Description, it, weights.description)),
+        );
+        fields
+    }
+
+    fn eval(
+        &self,
+        node: &QueryNode,
+        record: &BookmarkRecord,
+        highlights: Option<&mut Highlights>,
+    ) -> Option<i64> {
+        match node {
+            QueryNode::Term { value, .. } if value.is_empty() => Some(0),
+            QueryNode::Term {
+                field: None,
+                value,
+                phrase,
+            } => {
+                // Best weighted field, every matching field is highlighted
+                let mut best = None;
+                let mut found = Highlights::default();
+                for (field, text, weight) in self.fields(record) {
+                    let matched = if *phrase {
+                        find_ignore_case(text, value).map(|range| {
+                            let score = self.matcher.fuzzy_match(text, value).unwrap_or_default();
+                            (score, range.collect())
+                        })
+                    } else {
+                        self.matcher.fuzzy_indices(text, value)
+                    };
+                    if let Some((score, indices)) = matched {
+                        best = best.max(Some(score * weight));
+                        found.add(field, indices);
+                    }
+                }
+                if best.is_some()
+                    && let Some(highlights) = highlights
+                {
+                    highlights.merge(found);
+                }
+                best
+            }
+            QueryNode::Term {
+                field: Some(field),
+                value,
+                ..
+            } => {
+                let mut found = Highlights::default();
+                let mut contains = |field: MatchField, text: Option<&str>| match text
+                    .and_then(|text| find_ignore_case(text, value))
+                {
+                    Some(range) => {
+                        found.add(field, range);
+                        true
+                    }
+                    None => false,
+                };
+                let matched = match field {
+                    QueryField::Tag => {
+                        let mut matched = false;
+                        for (i, tag) in record.tags.iter().flatten().enumerate() {
+                            if tag.to_lowercase() == *value {
+                                found.add(MatchField::Tag(i), 0..tag.chars().count());
+                                matched = true;
+                            }
+                        }
+                        matched
+                    }
+                    QueryField::Path => {
+                        // Matches across folders, so it is checked on the joined path
+                        let joined = record.path.join("/");
+                        let matched = joined.to_lowercase().contains(value.as_str());
+                        for (i, folder) in record.path.iter().enumerate() {
+                            contains(MatchField::Path(i), Some(folder));
+                        }
+                        matched
+                    }
+                    QueryField::Url => contains(MatchField::Url, Some(&record.url)),
+                    QueryField::Host => {
+                        let host = host_of(&record.url).to_lowercase();
+                        let matched = host == *value || host.ends_with(&format!(".{value}"));
+                        if matched {
+                            contains(MatchField::Url, Some(&record.url));
+                        }
+                        matched
+                    }
+                    QueryField::Title => contains(MatchField::Title, record.title.as_deref()),
+                    QueryField::Desc => {
+                        contains(MatchField::Description, record.description.as_deref())
+                    }
+                    QueryField::Collection => record
+                        .collection
+                        .as_ref()
+                        .is_some_and(|it| it.to_lowercase() == *value),
+                };
+                if matched && let Some(highlights) = highlights {
+                    highlights.merge(found);
+                }
+                matched.then_some(0)
+            }
+            QueryNode::Not(node) => match self.eval(node, record, None) {
+                Some(_) => None,
+                None => Some(0),
+            },
+            // Children collect their own highlights, kept only when they match
+            QueryNode::And(nodes) => {
+                let mut found = Highlights::default();
+                let score = nodes
+                    .iter()
+                    .map(|node| self.eval(node, record, highlights.is_some().then_some(&mut found)))
+                    .sum::<Option<i64>>()?;
+                if let Some(highlights) = highlights {
+                    highlights.merge(found);
+                }
+                Some(score)
+            }
+            QueryNode::Or(nodes) => {
+                let mut highlights = highlights;
+                nodes
+                    .iter()
+                    .filter_map(|node| {
+                        let mut found = Highlights::default();
+                        let score =
+                            self.eval(node, record, highlights.is_some().then_some(&mut found))?;
+                        if let Some(highlights) = highlights.as_deref_mut() {
+                            highlights.merge(found);
+                        }
+                        Some(score)
+                    })
+                    .max()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(value: &str) -> QueryNode {
+        QueryNode::Term {
+            field: None,
+            value: value.to_string(),
+            phrase: false,
+        }
+    }
+
+    fn field(field: QueryField, value: &str, phrase: bool) -> QueryNode {
+        QueryNode::Term {
+            field: Some(field),
+            value: value.to_string(),
+            phrase,
+        }
+    }
+
+    fn not(node: QueryNode) -> QueryNode {
+        QueryNode::Not(Box::new(node))
+    }
+
+    fn record() -> BookmarkRecord {
+        BookmarkRecord {
+            path: vec!["Dev".to_string(), "Rust".to_string()],
+            title: Some("Async Rust Book".to_string()),
+            url: "https://user@docs.Example.com:8080/async".to_string(),
+            tags: Some(vec!["Rust".to_string(), "async io".to_string()]),
+            description: Some("A guide to futures".to_string()),
+            collection: Some("Work".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn matches(query: &str) -> bool {
+        Query::parse(query).matches(&record())
+    }
+
+    #[test]
+    fn parses_negation_or_and_parentheses() {
+        assert_eq!(
+            Query::parse("Rust -web NOT go").root(),
+            &QueryNode::And(vec![term("rust"), not(term("web")), not(term("go"))])
+        );
+        // AND binds tighter than OR
+        assert_eq!(
+            Query::parse("a b OR c | d").root(),
+            &QueryNode::Or(vec![
+                QueryNode::And(vec![term("a"), term("b")]),
+                term("c"),
+                term("d")
+            ])
+        );
+        assert_eq!(
+            Query::parse("a AND (b OR c) -(d e)").root(),
+            &QueryNode::And(vec![
+                term("a"),
+                QueryNode::Or(vec![term("b"), term("c")]),
+                not(QueryNode::And(vec![term("d"), term("e")])),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_phrases_and_fields() {
+        assert_eq!(
+            Query::parse(r#""Async Rust" title:"Rust Book" tag:IO host:docs.rs unknown:x"#).root(),
+            &QueryNode::And(vec![
+                QueryNode::Term {
+                    field: None,
+                    value: "async rust".to_string(),
+                    phrase: true,
+                },
+                field(QueryField::Title, "rust book", true),
+                field(QueryField::Tag, "io", false),
+                field(QueryField::Host, "docs.rs", false),
+                term("unknown:x"),
+            ])
+        );
+        assert_eq!(
+            Query::parse("description:guide").root(),
+            &field(QueryField::Desc, "guide", false)
+        );
+        // A phrase still being typed runs to the end
+        assert_eq!(
+            Query::parse(r#"tag:"async i"#).root(),
+            &field(QueryField::Tag, "async i", true)
+        );
+        // Escaped quotes and backslashes, other backslashes are kept
+        assert_eq!(
+            Query::parse(r#"tag:"say \"hi\" \\ C:\dir""#).root(),
+            &field(QueryField::Tag, r#"say "hi" \ c:\dir"#, true)
+        );
+    }
+
+    #[test]
+    fn unbalanced_and_empty_queries_are_lenient() {
+        assert_eq!(
+            Query::parse("(a OR b").root(),
+            &QueryNode::Or(vec![term("a"), term("b")])
+        );
+        assert_eq!(
+            Query::parse("a) b").root(),
+            &QueryNode::And(vec![term("a"), term("b")])
+        );
+        assert_eq!(Query::parse(")(").score(&record()), Some(0));
+        assert_eq!(Query::parse("").root(), &QueryNode::And(vec![]));
+        assert_eq!(Query::parse("   ").score(&record()), Some(0));
+        assert_eq!(Query::parse("OR").score(&record()), Some(0));
+        assert!(matches("rust -"));
+    }
+
+    #[test]
+    fn matches_fields() {
+        assert!(matches("tag:rust tag:\"ASYNC IO\""));
+        assert!(!matches("tag:rus"));
+        assert!(matches("host:example.com") && matches("host:docs.example.com"));
+        assert!(!matches("host:ample.com") && !matches("host:8080"));
+        assert!(matches("title:\"rust book\"") && !matches("title:guide"));
+        assert!(matches("path:dev/ru") && matches("url:ASYNC"));
+        assert!(matches("desc:futures") && matches("collection:work"));
+        assert!(matches("\"guide to\"") && !matches("\"guide futures\""));
+        assert!(matches("missing OR (rust -python)"));
+        assert!(!matches("rust -tag:rust"));
+        assert!(!matches("-(rust async)"));
+    }
+
+    #[test]
+    fn weights_scale_the_best_field() {
+        let record = record();
+        let query = Query::parse("book");
+        let default = query.score(&record).unwrap();
+        let no_title = FieldWeights {
+            title: 0,
+            ..Default::default()
+        };
+        assert_eq!(query.with_weights(no_title).score(&record), Some(0));
+        let double = FieldWeights {
+            title: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            Query::parse("book").with_weights(double).score(&record),
+            Some(default * 2)
+        );
+        // Field terms add nothing to the fuzzy score
+        assert_eq!(Query::parse("tag:rust").score(&record), Some(0));
+    }
+
+    #[test]
+    fn highlights_every_matching_field() {
+        let found = Query::parse("rust -python url:async")
+            .find(&record())
+            .unwrap();
+        assert_eq!(found.highlights.get(MatchField::Title), [6, 7, 8, 9]);
+        assert_eq!(found.highlights.get(MatchField::Tag(0)), [0, 1, 2, 3]);
+        assert_eq!(found.highlights.get(MatchField::Path(1)), [0, 1, 2, 3]);
+        assert_eq!(
+            found.highlights.get(MatchField::Url),
+            (35..40).collect::<Vec<_>>()
+        );
+        assert!(found.highlights.get(MatchField::Description).is_empty());
+
+        // Negated and failed alternatives leave no highlights
+        let found = Query::parse("-guide (book OR missing)")
+            .find(&BookmarkRecord {
+                title: Some("Book".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        let fields: Vec<_> = found.highlights.iter().map(|(field, _)| field).collect();
+        assert_eq!(fields, [MatchField::Title]);
+        // A failed AND inside an OR highlights none of its terms
+        let found = Query::parse("(rust missing) OR tokio")
+            .find(&BookmarkRecord {
+                title: Some("Rust and tokio".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(found.highlights.get(MatchField::Title), [9, 10, 11, 12, 13]);
+        assert!(
+            Query::parse("")
+                .find(&record())
+                .unwrap()
+                .highlights
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::{collections::HashMap, fmt, io, path::Path};
+
+use crate::{BookmarkRecord, load_from_fs, save_to_fs};
+
+/// Every tag with the number of records carrying it, most used first, then by name.
+///
+/// Tags are compared exactly, so `Rust` and `rust` are listed apart.
+pub fn tag_counts<'a>(
+    records: impl IntoIterator<Item = &'a BookmarkRecord>,
+) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for record in records {
+        for tag in record.tags.iter().flatten() {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    let mut counts: Vec<_> = counts
+        .into_iter()
+        .map(|(tag, count)| (tag.to_string(), count))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Maps every tag of `record` to its new name, `None` drops it.
+/// Merged duplicates keep the first position. Returns whether the tags changed.
+pub fn retag(record: &mut BookmarkRecord, mut rename: impl FnMut(&str) -> Option<String>) -> bool {
+    let Some(tags) = record.tags.as_ref() else {
+        return false;
+    };
+    let mut renamed: Vec<String> = vec![];
+    for tag in tags.iter().filter_map(|it| rename(it)) {
+        if !renamed.contains(&tag) {
+            renamed.push(tag);
+        }
+    }
+    if renamed == *tags {
+        return false;
+    }
+    record.tags = (!renamed.is_empty()).then_some(renamed);
+    true
+}
+
+/// Applies [`retag`] to every bookmark under `root` and saves the changed files.
+///
+/// Returns the number of changed files, files failing to load are skipped.
+#[tracing::instrument(skip(rename))]
+pub fn retag_all<P>(root: P, mut rename: impl FnMut(&str) -> Option<String>) -> io::Result<usize>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let mut count = 0;
+    for mut file in load_from_fs(root)? {
+        if retag(&mut file.content, &mut rename) {
+            save_to_fs(&file)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Renames the tags `from` to `to` in every file, several tags are merged into one
+pub fn rename_tags<P>(root: P, from: &[String], to: &str) -> io::Result<usize>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let to = to.trim();
+    if to.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Empty tag"));
+    }
+    retag_all(root, |tag| {
+        Some(match from.iter().any(|it| it == tag) {
+            true => to.to_string(),
+            false => tag.to_string(),
+        })
+    })
+}
+
+/// Removes the tags from every file
+pub fn delete_tags<P>(root: P, tags: &[String]) -> io::Result<usize>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    retag_all(root, |tag| {
+        (!tags.iter().any(|it| it == tag)).then(|| tag.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_in_fs, load_file};
+
+    fn record(title: &str, tags: &[&str]) -> BookmarkRecord {
+        BookmarkRecord {
+            title: Some(title.to_string()),
+            url: format!("https://example.com/{}", title.replace(' ', "-")),
+            tags: Some(tags.iter().map(|it| it.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn retag_keeps_the_first_position_of_merged_tags() {
+        let mut merged = record("A", &["go", "Rust", "web", "rust", "rust-lang"]);
+        let changed = retag(&mut merged, |tag| {
+            Some(match tag {
+                "Rust" | "rust-lang" => "rust".to_string(),
+                tag => tag.to_string(),
+            })
+        });
+        assert!(changed);
+        assert_eq!(merged.tags.as_deref().unwrap(), ["go", "rust", "web"]);
+
+        let mut unchanged = record("B", &["go", "web"]);
+        assert!(!retag(&mut unchanged, |tag| Some(tag.to_string())));
+        let mut untagged = BookmarkRecord::default();
+        assert!(!retag(&mut untagged, |_| None));
+
+        let mut emptied = record("C", &["old"]);
+        assert!(retag(&mut emptied, |_| None));
+        assert_eq!(emptied.tags, None);
+    }
+
+    #[test]
+    fn rename_merge_and_delete_tags() {
+        let root = tempfile::tempdir().unwrap();
+        let a = create_in_fs(root.path(), record("A", &["Rust", "rust-lang", "web"])).unwrap();
+        let b = create_in_fs(root.path(), record("B", &["rust", "old"])).unwrap();
+        let records = [a.content.clone(), b.content.clone()];
+        assert_eq!(tag_counts(&records)[0].1, 1);
+        assert_eq!(tag_counts(&records).len(), 5);
+
+        let from = ["Rust".to_string(), "rust-lang".to_string()];
+        assert_eq!(rename_tags(root.path(), &from, "rust").unwrap(), 1);
+        let a = load_file(root.path(), &a.relative_path).unwrap();
+        assert_eq!(a.content.tags.as_deref().unwrap(), ["rust", "web"]);
+
+        assert_eq!(delete_tags(root.path(), &["old".to_string()]).unwrap(), 1);
+        let b = load_file(root.path(), &b.relative_path).unwrap();
+        assert_eq!(b.content.tags.as_deref().unwrap(), ["rust"]);
+        assert_eq!(
+            tag_counts([&a.content, &b.content]),
+            [("rust".to_string(), 2), ("web".to_string(), 1)]
+        );
+        assert_eq!(
+            delete_tags(root.path(), &["missing".to_string()]).unwrap(),
+            0
+        );
+    }
+}
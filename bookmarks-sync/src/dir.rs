@@ -0,0 +1,238 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use bookmarks_data::TomlFileIterator;
+use serde::{Deserialize, Serialize};
+
+use crate::merge::Side;
+
+/// Default state file name inside the local directory. Not a `.toml` file so the loader skips it.
+pub const STATE_FILE: &str = ".bookmarks-sync-state";
+
+/// Reconciles two bookmark directories without git, e.g. a local copy and a shared mount.
+#[derive(Debug, Clone)]
+pub struct DirSync {
+    local: PathBuf,
+    remote: PathBuf,
+    state_path: PathBuf,
+}
+
+/// File hashes both sides agreed on after the last sync, per remote directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DirSyncState {
+    peers: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirAction {
+    /// Copy the file from the other side onto `to`
+    Copy {
+        path: PathBuf,
+        to: Side,
+    },
+    Delete {
+        path: PathBuf,
+        side: Side,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DirPlan {
+    pub actions: Vec<DirAction>,
+    /// Files changed on both sides, the most recently modified copy wins
+    pub conflicts: Vec<PathBuf>,
+    /// State to persist once the actions are applied
+    state: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+struct FileState {
+    hash: String,
+    modified: SystemTime,
+}
+
+impl fmt::Display for DirAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |side: &Side| match side {
+            Side::Local => "local",
+            Side::Remote => "remote",
+        };
+        match self {
+            DirAction::Copy { path, to } => {
+                write!(f, "copy   {} -> {}", path.to_string_lossy(), side(to))
+            }
+            DirAction::Delete { path, side: from } => {
+                write!(f, "delete {} from {}", path.to_string_lossy(), side(from))
+            }
+        }
+    }
+}
+
+fn key(relative_path: &Path) -> String {
+    relative_path
+        .iter()
+        .map(|it| it.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn scan(root: &Path) -> io::Result<BTreeMap<String, FileState>> {
+    let mut files = BTreeMap::new();
+    for entry in TomlFileIterator::new(root)? {
+        let entry = entry?;
+        let content = fs::read(&entry.path)?;
+        let modified = fs::metadata(&entry.path)?.modified()?;
+        files.insert(
+            key(&entry.relative_path),
+            FileState {
+                hash: blake3::hash(&content).to_hex().to_string(),
+                modified,
+            },
+        );
+    }
+    Ok(files)
+}
+
+/// Copies through a temporary file so readers never see a partial bookmark.
+fn copy_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = to.with_extension("toml.sync-tmp");
+    fs::copy(from, &tmp)?;
+    fs::rename(&tmp, to)
+}
+
+impl DirSync {
+    pub fn new(local: impl Into<PathBuf>, remote: impl Into<PathBuf>) -> Self {
+        let local = local.into();
+        Self {
+            state_path: local.join(STATE_FILE),
+            local,
+            remote: remote.into(),
+        }
+    }
+
+    pub fn with_state_path(mut self, state_path: impl Into<PathBuf>) -> Self {
+        self.state_path = state_path.into();
+        self
+    }
+
+    fn peer_key(&self) -> String {
+        self.remote
+            .canonicalize()
+            .unwrap_or_else(|_| self.remote.clone())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn load_state(&self) -> io::Result<DirSyncState> {
+        match fs::read_to_string(&self.state_path) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(DirSyncState::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Compares both directories with the last synced state, without touching any file.
+    #[tracing::instrument]
+    pub fn plan(&self) -> io::Result<DirPlan> {
+        let mut synced = self
+            .load_state()?
+            .peers
+            .remove(&self.peer_key())
+            .unwrap_or_default();
+        let local = scan(&self.local)?;
+        let remote = scan(&self.remote)?;
+        let paths: BTreeSet<&String> = local.keys().chain(remote.keys()).collect();
+
+        let mut plan = DirPlan::default();
+        for path in paths {
+            let base = synced.remove(path);
+            let copy = |to| DirAction::Copy {
+                path: PathBuf::from(path),
+                to,
+            };
+            let delete = |side| DirAction::Delete {
+                path: PathBuf::from(path),
+                side,
+            };
+            let (action, hash) = match (local.get(path), remote.get(path)) {
+                (Some(l), Some(r)) if l.hash == r.hash => (None, Some(&l.hash)),
+                (Some(l), None) if base.as_ref() == Some(&l.hash) => {
+                    (Some(delete(Side::Local)), None)
+                }
+                (Some(l), None) => (Some(copy(Side::Remote)), Some(&l.hash)),
+                (None, Some(r)) if base.as_ref() == Some(&r.hash) => {
+                    (Some(delete(Side::Remote)), None)
+                }
+                (None, Some(r)) => (Some(copy(Side::Local)), Some(&r.hash)),
+                (Some(l), Some(r)) if base.as_ref() == Some(&l.hash) => {
+                    (Some(copy(Side::Local)), Some(&r.hash))
+                }
+                (Some(l), Some(r)) if base.as_ref() == Some(&r.hash) => {
+                    (Some(copy(Side::Remote)), Some(&l.hash))
+                }
+                (Some(l), Some(r)) => {
+                    plan.conflicts.push(PathBuf::from(path));
+                    if l.modified >= r.modified {
+                        (Some(copy(Side::Remote)), Some(&l.hash))
+                    } else {
+                        (Some(copy(Side::Local)), Some(&r.hash))
+                    }
+                }
+                (None, None) => (None, None),
+            };
+            plan.actions.extend(action);
+            if let Some(hash) = hash {
+                plan.state.insert(path.clone(), hash.clone());
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Runs the planned actions and records the new synced state.
+    #[tracing::instrument(skip(plan))]
+    pub fn apply(&self, plan: &DirPlan) -> io::Result<()> {
+        let root = |side: &Side| match side {
+            Side::Local => &self.local,
+            Side::Remote => &self.remote,
+        };
+        for action in &plan.actions {
+            tracing::debug!("{action}");
+            match action {
+                DirAction::Copy { path, to } => {
+                    let from = match to {
+                        Side::Local => Side::Remote,
+                        Side::Remote => Side::Local,
+                    };
+                    copy_file(&root(&from).join(path), &root(to).join(path))?;
+                }
+                DirAction::Delete { path, side } => match fs::remove_file(root(side).join(path)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                },
+            }
+        }
+
+        let mut state = self.load_state()?;
+        state.peers.insert(self.peer_key(), plan.state.clone());
+        let content =
+            toml::to_string_pretty(&state).map_err(|e| io::Error::other(e.to_string()))?;
+        let tmp = self.state_path.with_extension("tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, &self.state_path)
+    }
+
+    /// Plans and applies in one go.
+    pub fn sync(&self) -> io::Result<DirPlan> {
+        let plan = self.plan()?;
+        self.apply(&plan)?;
+        Ok(plan)
+    }
+}
@@ -0,0 +1,538 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use bookmarks_data::BookmarkRecord;
+
+use crate::merge::{self, FieldConflict, MergeStrategy, Side};
+
+/// Bookmark files tracked by the sync, relative to the data directory.
+const PATHSPEC: &str = "*.toml";
+
+/// Data directory inside a git work tree, synced through the `git` executable.
+#[derive(Debug, Clone)]
+pub struct GitSync {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncStatus {
+    /// Uncommitted bookmark files
+    pub changed: usize,
+    /// Local commits not on the upstream branch
+    pub ahead: usize,
+    /// Upstream commits not merged locally (as of the last fetch)
+    pub behind: usize,
+    pub has_upstream: bool,
+    /// A rebase stopped on conflicts and waits for resolution
+    pub rebasing: bool,
+    pub conflicts: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    pub committed: bool,
+    pub pulled: bool,
+    pub pushed: bool,
+    /// Conflicted files merged automatically
+    pub merged: Vec<PathBuf>,
+    /// Files left conflicted by `pull --rebase`
+    pub conflicts: Vec<FileConflict>,
+}
+
+/// A conflicted bookmark file, relative to the data directory
+#[derive(Debug, Clone)]
+pub struct FileConflict {
+    pub path: PathBuf,
+    /// `None` when the file can not be merged by field,
+    /// e.g. it was deleted on one side or does not parse
+    pub merged: Option<BookmarkRecord>,
+    pub fields: Vec<FieldConflict>,
+}
+
+impl FileConflict {
+    pub fn is_resolved(&self) -> bool {
+        self.merged.is_some() && self.fields.is_empty()
+    }
+}
+
+impl fmt::Display for SyncStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.conflicts.is_empty() {
+            return write!(f, "{} conflicted", self.conflicts.len());
+        }
+        let mut parts = vec![];
+        if self.changed > 0 {
+            parts.push(format!("{} changed", self.changed));
+        }
+        if self.ahead > 0 {
+            parts.push(format!("{} ahead", self.ahead));
+        }
+        if self.behind > 0 {
+            parts.push(format!("{} behind", self.behind));
+        }
+        if parts.is_empty() {
+            parts.push(
+                if self.has_upstream {
+                    "up to date"
+                } else {
+                    "no upstream"
+                }
+                .to_string(),
+            );
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+impl GitSync {
+    /// Opens the git work tree containing `dir`.
+    #[tracing::instrument]
+    pub fn open<P>(dir: P) -> io::Result<Self>
+    where
+        P: AsRef<Path> + fmt::Debug,
+    {
+        let sync = Self {
+            dir: dir.as_ref().to_path_buf(),
+        };
+        let inside = sync.git(["rev-parse", "--is-inside-work-tree"])?;
+        if inside.trim() != "true" {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{dir:?} is not inside a git work tree"),
+            ));
+        }
+        Ok(sync)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn command<I, S>(&self, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let mut command = Command::new("git");
+        command
+            .current_dir(&self.dir)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env("GIT_EDITOR", "true")
+            .args(args);
+        command
+    }
+
+    fn output<I, S>(&self, args: I) -> io::Result<Output>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let mut command = self.command(args);
+        tracing::debug!("Running {command:?}");
+        command.output()
+    }
+
+    /// Runs git and returns stdout, failing on a non-zero exit code.
+    fn git<I, S>(&self, args: I) -> io::Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let output = self.output(args)?;
+        if !output.status.success() {
+            return Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn git_path_exists(&self, name: &str) -> io::Result<bool> {
+        let path = self.git(["rev-parse", "--git-path", name])?;
+        Ok(self.dir.join(path.trim()).exists())
+    }
+
+    fn has_upstream(&self) -> bool {
+        self.git(["rev-parse", "--abbrev-ref", "@{upstream}"])
+            .is_ok()
+    }
+
+    /// `false` on a fresh branch without commits
+    fn has_head(&self) -> bool {
+        self.git(["rev-parse", "--verify", "--quiet", "HEAD"])
+            .is_ok()
+    }
+
+    /// Tracks the branch of the same name on the first remote, if it exists there.
+    ///
+    /// Covers clones of an empty repository, which start without an upstream.
+    fn track_remote(&self) -> io::Result<bool> {
+        let remotes = self.git(["remote"])?;
+        let Some(remote) = remotes.lines().next() else {
+            return Ok(false);
+        };
+        self.git(["fetch", "--quiet", remote])?;
+        let branch = self.git(["symbolic-ref", "--short", "HEAD"])?;
+        let branch = branch.trim();
+        let remote_ref = format!("refs/remotes/{remote}/{branch}");
+        if self
+            .git(["rev-parse", "--verify", "--quiet", &remote_ref])
+            .is_err()
+        {
+            return Ok(false);
+        }
+        self.git(["config", &format!("branch.{branch}.remote"), remote])?;
+        self.git([
+            "config",
+            &format!("branch.{branch}.merge"),
+            &format!("refs/heads/{branch}"),
+        ])?;
+        Ok(true)
+    }
+
+    /// Changed bookmark files, relative to the work tree root.
+    fn changed_files(&self) -> io::Result<Vec<String>> {
+        let status = self.git([
+            "status",
+            "--porcelain",
+            "-z",
+            "--untracked-files=all",
+            "--",
+            PATHSPEC,
+        ])?;
+        let mut files = vec![];
+        let mut entries = status.split('\0').filter(|it| !it.is_empty());
+        while let Some(entry) = entries.next() {
+            let (code, path) = entry.split_at_checked(3).unwrap_or_default();
+            files.push(path.to_string());
+            if code.starts_with(['R', 'C'])
+                && let Some(original) = entries.next()
+            {
+                files.push(original.to_string());
+            }
+        }
+        Ok(files)
+    }
+
+    /// Stages every changed bookmark file.
+    fn stage_changes(&self) -> io::Result<()> {
+        let files = self.changed_files()?;
+        if !files.is_empty() {
+            self.git(
+                ["add", "--all", "--"]
+                    .into_iter()
+                    .map(str::to_string)
+                    .chain(files.iter().map(|it| format!(":(top){it}"))),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Conflicted bookmark files, relative to the data directory.
+    pub fn conflicts(&self) -> io::Result<Vec<PathBuf>> {
+        Ok(self
+            .git([
+                "diff",
+                "--name-only",
+                "--relative",
+                "--diff-filter=U",
+                "--",
+                PATHSPEC,
+            ])?
+            .lines()
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    pub fn is_rebasing(&self) -> io::Result<bool> {
+        Ok(self.git_path_exists("rebase-merge")? || self.git_path_exists("rebase-apply")?)
+    }
+
+    #[tracing::instrument]
+    pub fn status(&self) -> io::Result<SyncStatus> {
+        let changed = self.changed_files()?.len();
+        let has_upstream = self.has_upstream();
+        let (ahead, behind) = if has_upstream && !self.has_head() {
+            let behind = self.git(["rev-list", "--count", "@{upstream}"])?;
+            (0, behind.trim().parse().unwrap_or(0))
+        } else if has_upstream {
+            let counts = self.git(["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])?;
+            let mut counts = counts.split_whitespace().map(|it| it.parse().unwrap_or(0));
+            (counts.next().unwrap_or(0), counts.next().unwrap_or(0))
+        } else {
+            (0, 0)
+        };
+        Ok(SyncStatus {
+            changed,
+            ahead,
+            behind,
+            has_upstream,
+            rebasing: self.is_rebasing()?,
+            conflicts: self.conflicts()?,
+        })
+    }
+
+    /// Stages every bookmark file change and commits it.
+    ///
+    /// Returns `false` when there was nothing to commit.
+    #[tracing::instrument]
+    pub fn commit_changes(&self, message: &str) -> io::Result<bool> {
+        self.stage_changes()?;
+        let staged = self.output(["diff", "--cached", "--quiet"])?;
+        if staged.status.success() {
+            return Ok(false);
+        }
+        self.git(["commit", "--quiet", "-m", message])?;
+        Ok(true)
+    }
+
+    /// Fetches and rebases local commits onto the upstream branch.
+    ///
+    /// On conflicts the rebase is left in progress and the conflicted files are returned,
+    /// see [`GitSync::continue_rebase`] and [`GitSync::abort_rebase`].
+    #[tracing::instrument]
+    pub fn pull(&self) -> io::Result<Vec<PathBuf>> {
+        let output = self.output(["pull", "--rebase", "--autostash", "--quiet"])?;
+        if output.status.success() {
+            return Ok(vec![]);
+        }
+        let conflicts = self.conflicts()?;
+        if conflicts.is_empty() {
+            return Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        tracing::warn!("Pull stopped on conflicts {conflicts:?}");
+        Ok(conflicts)
+    }
+
+    /// Pushes the current branch, setting the upstream on first push.
+    #[tracing::instrument]
+    pub fn push(&self) -> io::Result<()> {
+        if self.has_upstream() {
+            self.git(["push", "--quiet"])?;
+        } else {
+            let remotes = self.git(["remote"])?;
+            let Some(remote) = remotes.lines().next() else {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "No git remote configured",
+                ));
+            };
+            self.git(["push", "--quiet", "--set-upstream", remote, "HEAD"])?;
+        }
+        Ok(())
+    }
+
+    /// Content of a conflicted file at an index stage (1: base, 2: upstream, 3: replayed commit).
+    fn stage_content(&self, stage: u8, path: &Path) -> Option<String> {
+        self.git(["show", &format!(":{stage}:./{}", path.to_string_lossy())])
+            .ok()
+    }
+
+    fn commit_time(&self, rev: &str, path: &Path) -> i64 {
+        self.git([
+            "log",
+            "-1",
+            "--format=%ct",
+            rev,
+            "--",
+            &path.to_string_lossy(),
+        ])
+        .ok()
+        .and_then(|it| it.trim().parse().ok())
+        .unwrap_or_default()
+    }
+
+    /// Field level view of every conflicted bookmark file during a stopped rebase.
+    ///
+    /// The commit being replayed is the local side, the upstream branch the remote side.
+    #[tracing::instrument]
+    pub fn conflict_details(&self, strategy: MergeStrategy) -> io::Result<Vec<FileConflict>> {
+        let parse = |content: Option<String>| {
+            content.and_then(|it| bookmarks_data::from_toml_str(&it).ok())
+        };
+        self.conflicts()?
+            .into_iter()
+            .map(|path| {
+                let base = parse(self.stage_content(1, &path));
+                let remote = parse(self.stage_content(2, &path));
+                let local = parse(self.stage_content(3, &path));
+                let (Some(local), Some(remote)) = (local, remote) else {
+                    return Ok(FileConflict {
+                        path,
+                        merged: None,
+                        fields: vec![],
+                    });
+                };
+                let winner =
+                    if self.commit_time("HEAD", &path) > self.commit_time("REBASE_HEAD", &path) {
+                        Side::Remote
+                    } else {
+                        Side::Local
+                    };
+                let result = merge::merge_records(base.as_ref(), &local, &remote, strategy, winner);
+                Ok(FileConflict {
+                    path,
+                    merged: Some(result.record),
+                    fields: result.conflicts,
+                })
+            })
+            .collect()
+    }
+
+    /// Writes a resolved record over a conflicted file and marks it resolved.
+    #[tracing::instrument]
+    pub fn resolve(&self, path: &Path, record: &BookmarkRecord) -> io::Result<()> {
+        record.validate()?;
+        fs::write(self.dir.join(path), bookmarks_data::to_toml_string(record)?)?;
+        self.git(["add", "--", &path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// Merges conflicted files field by field.
+    ///
+    /// Every mergeable file is written, the ones without remaining field conflicts
+    /// are marked resolved. Returns the files still needing attention.
+    #[tracing::instrument]
+    pub fn auto_merge(&self, strategy: MergeStrategy) -> io::Result<Vec<FileConflict>> {
+        let mut unresolved = vec![];
+        for conflict in self.conflict_details(strategy)? {
+            match &conflict.merged {
+                Some(record) if conflict.fields.is_empty() => {
+                    self.resolve(&conflict.path, record)?;
+                }
+                Some(record) => {
+                    fs::write(
+                        self.dir.join(&conflict.path),
+                        bookmarks_data::to_toml_string(record)?,
+                    )?;
+                    unresolved.push(conflict);
+                }
+                None => unresolved.push(conflict),
+            }
+        }
+        Ok(unresolved)
+    }
+
+    /// Stages resolved files and continues a stopped rebase.
+    ///
+    /// Conflicted files count as resolved once they parse and hold no conflict markers.
+    /// Returns the conflicts of the next replayed commit, if any.
+    #[tracing::instrument]
+    pub fn continue_rebase(&self) -> io::Result<Vec<PathBuf>> {
+        let unresolved: Vec<_> = self
+            .conflicts()?
+            .into_iter()
+            .filter(|path| match fs::read_to_string(self.dir.join(path)) {
+                Ok(content) => {
+                    content.contains("<<<<<<< ") || bookmarks_data::from_toml_str(&content).is_err()
+                }
+                Err(_) => false,
+            })
+            .collect();
+        if !unresolved.is_empty() {
+            return Err(io::Error::other(format!(
+                "Unresolved conflicts in {unresolved:?}"
+            )));
+        }
+        self.stage_changes()?;
+        let output = self.output(["rebase", "--continue"])?;
+        if output.status.success() {
+            return Ok(vec![]);
+        }
+        let conflicts = self.conflicts()?;
+        if conflicts.is_empty() && self.is_rebasing()? {
+            return Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(conflicts)
+    }
+
+    #[tracing::instrument]
+    pub fn abort_rebase(&self) -> io::Result<()> {
+        self.git(["rebase", "--abort"])?;
+        Ok(())
+    }
+
+    /// Auto merges and continues the rebase until it completes or needs the user.
+    fn merge_and_continue(
+        &self,
+        mut conflicts: Vec<PathBuf>,
+        strategy: MergeStrategy,
+        report: &mut SyncReport,
+    ) -> io::Result<()> {
+        while !conflicts.is_empty() {
+            report.conflicts = self.auto_merge(strategy)?;
+            if !report.conflicts.is_empty() {
+                return Ok(());
+            }
+            report.merged.extend(conflicts);
+            conflicts = self.continue_rebase()?;
+        }
+        Ok(())
+    }
+
+    /// Pushes when there are local commits missing upstream.
+    fn push_if_ahead(&self) -> io::Result<bool> {
+        let status = self.status()?;
+        if self.has_head() && (!status.has_upstream || status.ahead > 0) {
+            self.push()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Commit, pull with rebase and push.
+    ///
+    /// Conflicting bookmark files are merged with [`GitSync::auto_merge`], the sync stops
+    /// before pushing when some conflicts are left.
+    #[tracing::instrument]
+    pub fn sync(
+        &self,
+        message: &str,
+        push: bool,
+        strategy: MergeStrategy,
+    ) -> io::Result<SyncReport> {
+        if self.is_rebasing()? {
+            return Err(io::Error::other(
+                "A rebase is in progress, resolve conflicts first",
+            ));
+        }
+        let mut report = SyncReport {
+            committed: self.commit_changes(message)?,
+            ..Default::default()
+        };
+        if self.has_upstream() || self.track_remote()? {
+            let conflicts = self.pull()?;
+            report.pulled = true;
+            self.merge_and_continue(conflicts, strategy, &mut report)?;
+            if !report.conflicts.is_empty() {
+                return Ok(report);
+            }
+        }
+        if push {
+            report.pushed = self.push_if_ahead()?;
+        }
+        Ok(report)
+    }
+
+    /// Finishes a sync stopped on conflicts once they are resolved.
+    #[tracing::instrument]
+    pub fn resume(&self, push: bool, strategy: MergeStrategy) -> io::Result<SyncReport> {
+        let mut report = SyncReport {
+            pulled: true,
+            ..Default::default()
+        };
+        let conflicts = self.continue_rebase()?;
+        self.merge_and_continue(conflicts, strategy, &mut report)?;
+        if report.conflicts.is_empty() && push {
+            report.pushed = self.push_if_ahead()?;
+        }
+        Ok(report)
+    }
+}
@@ -1,14 +1,153 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+mod dir;
+mod git;
+mod merge;
+
+pub use dir::{DirAction, DirPlan, DirSync, STATE_FILE};
+pub use git::{FileConflict, GitSync, SyncReport, SyncStatus};
+pub use merge::{Field, FieldConflict, MergeResult, MergeStrategy, Side, merge_records};
 
 #[cfg(test)]
 mod tests {
+    use std::{fs, path::Path, process::Command};
+
     use super::*;
 
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .current_dir(dir)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "{status:?}");
+    }
+
+    /// Bare remote with two clones
+    fn setup() -> (tempfile::TempDir, GitSync, GitSync) {
+        let root = tempfile::tempdir().unwrap();
+        git(root.path(), &["init", "--quiet", "--bare", "remote.git"]);
+        for name in ["a", "b"] {
+            git(root.path(), &["clone", "--quiet", "remote.git", name]);
+            let dir = root.path().join(name);
+            git(&dir, &["config", "user.name", name]);
+            git(&dir, &["config", "user.email", "test@example.com"]);
+            git(&dir, &["config", "commit.gpgsign", "false"]);
+        }
+        let a = GitSync::open(root.path().join("a")).unwrap();
+        let b = GitSync::open(root.path().join("b")).unwrap();
+        (root, a, b)
+    }
+
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn sync_round_trip() {
+        let (_root, a, b) = setup();
+        fs::create_dir(a.dir().join("rust")).unwrap();
+        fs::write(a.dir().join("rust/book.toml"), "url = \"https://a\"\n").unwrap();
+        fs::write(a.dir().join("notes.txt"), "ignored").unwrap();
+        assert_eq!(a.status().unwrap().changed, 1);
+
+        let report = a.sync("add book", true, MergeStrategy::Markers).unwrap();
+        assert!(report.committed && report.pushed);
+        assert_eq!(a.status().unwrap().changed, 0);
+
+        let report = b.sync("nothing", true, MergeStrategy::Markers).unwrap();
+        assert!(!report.committed && report.pulled && !report.pushed);
+        assert!(b.dir().join("rust/book.toml").exists());
+        assert!(!b.dir().join("notes.txt").exists());
+    }
+
+    #[test]
+    fn sync_reports_conflicts() {
+        let (_root, a, b) = setup();
+        fs::write(a.dir().join("book.toml"), "url = \"https://a\"\n").unwrap();
+        a.sync("add", true, MergeStrategy::Markers).unwrap();
+        b.sync("pull", true, MergeStrategy::Markers).unwrap();
+
+        fs::write(a.dir().join("book.toml"), "url = \"https://from-a\"\n").unwrap();
+        a.sync("edit a", true, MergeStrategy::Markers).unwrap();
+        fs::write(b.dir().join("book.toml"), "url = \"https://from-b\"\n").unwrap();
+        let report = b.sync("edit b", true, MergeStrategy::Markers).unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].path, Path::new("book.toml"));
+        assert_eq!(report.conflicts[0].fields[0].field, Field::Url);
+        assert!(!report.pushed);
+
+        let status = b.status().unwrap();
+        assert!(status.rebasing);
+        assert_eq!(status.conflicts.len(), 1);
+        assert!(b.sync("again", true, MergeStrategy::Markers).is_err());
+        assert!(b.continue_rebase().is_err());
+
+        b.abort_rebase().unwrap();
+        assert!(!b.status().unwrap().rebasing);
+    }
+
+    #[test]
+    fn sync_merges_fields() {
+        let (_root, a, b) = setup();
+        let book = "url = \"https://a\"\ntitle = \"Book\"\ntags = [\"x\", \"y\"]\n";
+        fs::write(a.dir().join("book.toml"), book).unwrap();
+        a.sync("add", true, MergeStrategy::Markers).unwrap();
+        b.sync("pull", true, MergeStrategy::Markers).unwrap();
+
+        let edit_a = "url = \"https://a\"\ntitle = \"Book A\"\ntags = [\"x\", \"y\", \"a\"]\n";
+        fs::write(a.dir().join("book.toml"), edit_a).unwrap();
+        a.sync("edit a", true, MergeStrategy::Markers).unwrap();
+        let edit_b =
+            "url = \"https://a\"\ntitle = \"Book\"\ntags = [\"y\", \"b\"]\ndescription = \"B\"\n";
+        fs::write(b.dir().join("book.toml"), edit_b).unwrap();
+
+        let report = b.sync("edit b", true, MergeStrategy::Markers).unwrap();
+        assert!(report.conflicts.is_empty());
+        assert_eq!(report.merged, vec![Path::new("book.toml")]);
+        assert!(report.pushed);
+
+        let merged = bookmarks_data::load_file(b.dir(), "book.toml")
+            .unwrap()
+            .content;
+        assert_eq!(merged.title.as_deref(), Some("Book A"));
+        assert_eq!(merged.description.as_deref(), Some("B"));
+        assert_eq!(merged.tags.unwrap(), ["y", "b", "a"]);
+    }
+
+    #[test]
+    fn dir_sync_propagates_changes_and_deletions() {
+        let root = tempfile::tempdir().unwrap();
+        let (local, remote) = (root.path().join("local"), root.path().join("remote"));
+        fs::create_dir_all(local.join("work")).unwrap();
+        fs::create_dir_all(&remote).unwrap();
+        fs::write(local.join("work/a.toml"), "url = \"https://a\"\n").unwrap();
+        fs::write(remote.join("b.toml"), "url = \"https://b\"\n").unwrap();
+        let sync = DirSync::new(&local, &remote);
+
+        let plan = sync.plan().unwrap();
+        assert_eq!(plan.actions.len(), 2);
+        assert!(!remote.join("work/a.toml").exists(), "plan is a dry run");
+
+        sync.sync().unwrap();
+        assert!(remote.join("work/a.toml").exists());
+        assert!(local.join("b.toml").exists());
+        assert!(sync.plan().unwrap().actions.is_empty());
+
+        fs::remove_file(remote.join("work/a.toml")).unwrap();
+        fs::write(remote.join("b.toml"), "url = \"https://b2\"\n").unwrap();
+        let plan = sync.sync().unwrap();
+        assert_eq!(
+            plan.actions,
+            vec![
+                DirAction::Copy {
+                    path: "b.toml".into(),
+                    to: Side::Local
+                },
+                DirAction::Delete {
+                    path: "work/a.toml".into(),
+                    side: Side::Local
+                },
+            ]
+        );
+        assert!(!local.join("work/a.toml").exists());
+        assert_eq!(
+            fs::read_to_string(local.join("b.toml")).unwrap(),
+            "url = \"https://b2\"\n"
+        );
     }
 }
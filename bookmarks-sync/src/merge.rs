@@ -0,0 +1,209 @@
+use bookmarks_data::BookmarkRecord;
+use strum::{Display, EnumString};
+
+/// How diverging `title` and `description` edits are combined.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Keep both versions between git style conflict markers and report a conflict
+    #[default]
+    Markers,
+    /// Keep the version from the most recent commit
+    LastWriterWins,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Field {
+    Title,
+    Url,
+    Description,
+}
+
+/// A field changed differently on both sides
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldConflict {
+    pub field: Field,
+    pub base: Option<String>,
+    pub local: Option<String>,
+    pub remote: Option<String>,
+}
+
+impl FieldConflict {
+    pub fn value(&self, side: Side) -> Option<&str> {
+        match side {
+            Side::Local => self.local.as_deref(),
+            Side::Remote => self.remote.as_deref(),
+        }
+    }
+
+    /// Resolves the conflict on `record` with the value from `side`.
+    pub fn apply(&self, record: &mut BookmarkRecord, side: Side) {
+        let value = self.value(side).map(str::to_string);
+        match self.field {
+            Field::Title => record.title = value,
+            Field::Url => record.url = value.unwrap_or_default(),
+            Field::Description => record.description = value,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    pub record: BookmarkRecord,
+    /// Fields left for the user, the record holds both values between conflict markers.
+    pub conflicts: Vec<FieldConflict>,
+}
+
+fn merge_value<T: PartialEq + Clone>(base: Option<&T>, local: &T, remote: &T) -> Result<T, ()> {
+    if local == remote || base == Some(remote) {
+        Ok(local.clone())
+    } else if base == Some(local) {
+        Ok(remote.clone())
+    } else {
+        Err(())
+    }
+}
+
+fn with_markers(local: Option<&str>, remote: Option<&str>) -> String {
+    format!(
+        "<<<<<<< local\n{}\n=======\n{}\n>>>>>>> remote",
+        local.unwrap_or_default(),
+        remote.unwrap_or_default()
+    )
+}
+
+/// Three-way set merge: additions from either side are kept, removals from either side win.
+fn merge_tags(
+    base: Option<&[String]>,
+    local: Option<&[String]>,
+    remote: Option<&[String]>,
+) -> Option<Vec<String>> {
+    let base = base.unwrap_or_default();
+    let (local, remote) = (local.unwrap_or_default(), remote.unwrap_or_default());
+    let mut tags: Vec<String> = vec![];
+    for tag in local.iter().chain(remote) {
+        let keep = (local.contains(tag) && remote.contains(tag)) || !base.contains(tag);
+        if keep && !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    (!tags.is_empty()).then_some(tags)
+}
+
+/// Field-aware three-way merge of two edits of the same bookmark.
+///
+/// `winner` picks the side for text conflicts under [`MergeStrategy::LastWriterWins`].
+pub fn merge_records(
+    base: Option<&BookmarkRecord>,
+    local: &BookmarkRecord,
+    remote: &BookmarkRecord,
+    strategy: MergeStrategy,
+    winner: Side,
+) -> MergeResult {
+    let mut conflicts = vec![];
+    let mut text_field =
+        |field: Field, get: fn(&BookmarkRecord) -> Option<&String>| -> Option<String> {
+            let (local, remote) = (get(local).cloned(), get(remote).cloned());
+            let base = base.map(|it| get(it).cloned());
+            match merge_value(base.as_ref(), &local, &remote) {
+                Ok(value) => value,
+                Err(()) if strategy == MergeStrategy::LastWriterWins => match winner {
+                    Side::Local => local,
+                    Side::Remote => remote,
+                },
+                Err(()) => {
+                    let merged = with_markers(local.as_deref(), remote.as_deref());
+                    conflicts.push(FieldConflict {
+                        field,
+                        base: base.flatten(),
+                        local,
+                        remote,
+                    });
+                    Some(merged)
+                }
+            }
+        };
+    let title = text_field(Field::Title, |it| it.title.as_ref());
+    let description = text_field(Field::Description, |it| it.description.as_ref());
+
+    let url = match merge_value(base.map(|it| &it.url), &local.url, &remote.url) {
+        Ok(url) => url,
+        Err(()) => {
+            conflicts.push(FieldConflict {
+                field: Field::Url,
+                base: base.map(|it| it.url.clone()),
+                local: Some(local.url.clone()),
+                remote: Some(remote.url.clone()),
+            });
+            with_markers(Some(&local.url), Some(&remote.url))
+        }
+    };
+
+    // Embeddings are derived data, drop them when both sides changed
+    let embeddings = merge_value(
+        base.map(|it| &it.embeddings),
+        &local.embeddings,
+        &remote.embeddings,
+    )
+    .unwrap_or_default();
+
+    MergeResult {
+        record: BookmarkRecord {
+            path: local.path.clone(),
+            title,
+            url,
+            tags: merge_tags(
+                base.and_then(|it| it.tags.as_deref()),
+                local.tags.as_deref(),
+                remote.tags.as_deref(),
+            ),
+            description,
+            embeddings,
+            collection: local.collection.clone(),
+        },
+        conflicts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_text_strategies() {
+        let record = |title: &str| BookmarkRecord {
+            url: "https://a".to_string(),
+            title: Some(title.to_string()),
+            ..Default::default()
+        };
+        let (base, local, remote) = (record("base"), record("local"), record("remote"));
+
+        let result = merge_records(
+            Some(&base),
+            &local,
+            &remote,
+            MergeStrategy::Markers,
+            Side::Local,
+        );
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].field, Field::Title);
+        assert!(result.record.title.unwrap().contains("=======\nremote"));
+
+        let result = merge_records(
+            Some(&base),
+            &local,
+            &remote,
+            MergeStrategy::LastWriterWins,
+            Side::Remote,
+        );
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.record.title.as_deref(), Some("remote"));
+    }
+}